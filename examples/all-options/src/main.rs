@@ -5,7 +5,9 @@
     dylib = "lib",
     lib_dir = if cfg!(debug_assertions) { "target/debug" } else { "target/release" },
     file_watch_debounce = 500,
-    loaded_lib_name_template = "{lib_name}_hot_{pid}_{load_counter}"
+    loaded_lib_name_template = "{lib_name}_hot_{pid}_{load_counter}",
+    // keep up to 8 old library versions loaded
+    leak_max_versions = 8
 )]
 mod hot_lib {
     pub use lib::*;
@@ -32,9 +34,16 @@ mod hot_lib {
     // function will return true only _once_ after a reload.
     #[lib_updated]
     pub fn was_updated() -> bool {}
+
+    // Loads the library and starts watching it right away. Errors like a wrong
+    // lib_dir are returned instead of panicking on the first call.
+    #[lib_init]
+    pub fn init() -> Result<(), hot_lib_reloader::HotReloaderError> {}
 }
 
 fn main() {
+    hot_lib::init().expect("failed to load the hot library");
+
     let mut state = hot_lib::State { counter: 0 };
     loop {
         hot_lib::do_stuff(&mut state);
@@ -6,7 +6,8 @@ use lib::*;
 #[cfg(feature = "reload")]
 #[hot_lib_reloader::hot_module(dylib = "lib")]
 mod hot_lib {
-    hot_functions_from_file!("lib/src/lib.rs");
+    // the library is built with `reload` when hot-reloading
+    hot_functions_from_file!("lib/src/lib.rs", features = ["reload"]);
 }
 
 fn main() {
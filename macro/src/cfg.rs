@@ -0,0 +1,213 @@
+//! Evaluation of `#[cfg(...)]` predicates of the library source that is
+//! scanned for exports.
+//!
+//! Proc macros do not know the configuration of the build. We use what is
+//! available in the environment: `CARGO_CFG_<NAME>` like build scripts get
+//! them, e.g. `CARGO_CFG_TARGET_OS=linux` or `CARGO_CFG_DEBUG_ASSERTIONS`.
+//! Those are not set when compiling a crate but can be forwarded by a build
+//! script using `cargo:rustc-env`. Predicates that cannot be decided are
+//! passed on to the generated code as `#[cfg(...)]` so the compiler decides
+//! with the configuration of the executable.
+//!
+//! Features are not shared between the library and the executable, so
+//! `feature = "..."` is only decided with the library features listed in
+//! `features = [...]` of the source macro and never passed on.
+
+use std::cell::RefCell;
+
+use proc_macro2::Span;
+use syn::{Attribute, Meta, Result};
+
+/// The configuration of the library build that predicates are evaluated
+/// with.
+pub struct LibConfig {
+    /// Assume that functions are unmangled by other means than an attribute,
+    /// `ignore_no_mangle = true` of the source macro.
+    pub ignore_no_mangle: bool,
+    /// The enabled features of the library, given with `features = [...]`.
+    /// Without it all features are off.
+    pub features: Option<Vec<String>>,
+    /// Predicates that could not be evaluated properly, reported as warnings
+    /// at `span`.
+    warnings: RefCell<Vec<String>>,
+    span: Span,
+}
+
+impl LibConfig {
+    pub fn new(span: Span) -> Self {
+        Self {
+            ignore_no_mangle: false,
+            features: None,
+            warnings: RefCell::default(),
+            span,
+        }
+    }
+
+    fn warn(&self, message: String) {
+        let mut warnings = self.warnings.borrow_mut();
+        if !warnings.contains(&message) {
+            warnings.push(message);
+        }
+    }
+
+    /// Code that makes the compiler report the warnings. There is no API for
+    /// warnings of proc macros, so this uses a deprecated constant.
+    pub fn warnings(&self) -> proc_macro2::TokenStream {
+        let span = self.span;
+        let warnings = self.warnings.borrow();
+        quote::quote_spanned! {span=>
+            #(
+                const _: () = {
+                    #[deprecated(note = #warnings)]
+                    #[allow(non_upper_case_globals)]
+                    const hot_lib_reloader_warning: () = ();
+                    hot_lib_reloader_warning
+                };
+            )*
+        }
+    }
+}
+
+/// The result of evaluating the `cfg` predicates of an item.
+pub enum Cfg {
+    Enabled,
+    Disabled,
+    /// The predicate could not be evaluated and is left to the compiler.
+    Unknown(Box<Meta>),
+}
+
+impl Cfg {
+    /// Evaluates all `#[cfg(...)]` attributes of an item.
+    pub fn of_item(attrs: &[Attribute], config: &LibConfig) -> Result<Self> {
+        let mut cfg = Self::Enabled;
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("cfg")) {
+            cfg = cfg.and(Self::of_predicate(&attr.parse_args::<Meta>()?, config)?);
+        }
+        Ok(cfg)
+    }
+
+    /// Evaluates a single predicate, e.g. of `#[cfg_attr(predicate, ...)]`.
+    pub fn of_predicate(predicate: &Meta, config: &LibConfig) -> Result<Self> {
+        Ok(match evaluate(predicate, config)? {
+            Some(true) => Self::Enabled,
+            Some(false) => Self::Disabled,
+            None => Self::Unknown(Box::new(predicate.clone())),
+        })
+    }
+
+    /// Both `self` and `other` have to apply.
+    pub fn and(self, other: Self) -> Self {
+        match (self, other) {
+            (Self::Disabled, _) | (_, Self::Disabled) => Self::Disabled,
+            (Self::Enabled, cfg) | (cfg, Self::Enabled) => cfg,
+            (Self::Unknown(a), Self::Unknown(b)) => {
+                Self::Unknown(Box::new(syn::parse_quote! { all(#a, #b) }))
+            }
+        }
+    }
+
+    /// The `#[cfg(...)]` attribute to add to the generated code, if any.
+    pub fn attr(&self) -> Option<Attribute> {
+        match self {
+            Self::Unknown(predicate) => Some(syn::parse_quote! { #[cfg(#predicate)] }),
+            _ => None,
+        }
+    }
+}
+
+/// The `#[cfg(...)]` attributes in `attrs`.
+pub fn cfg_attrs(attrs: &[Attribute]) -> impl Iterator<Item = &Attribute> {
+    attrs.iter().filter(|attr| attr.path().is_ident("cfg"))
+}
+
+/// Evaluates a cfg predicate, `None` if it depends on something unknown.
+fn evaluate(predicate: &Meta, config: &LibConfig) -> Result<Option<bool>> {
+    let name = predicate
+        .path()
+        .get_ident()
+        .map(ToString::to_string)
+        .unwrap_or_default();
+
+    match predicate {
+        Meta::List(list) if matches!(name.as_str(), "all" | "any" | "not") => {
+            let nested = list.parse_args_with(
+                syn::punctuated::Punctuated::<Meta, syn::Token![,]>::parse_terminated,
+            )?;
+            let results = nested
+                .iter()
+                .map(|predicate| evaluate(predicate, config))
+                .collect::<Result<Vec<_>>>()?;
+            Ok(match name.as_str() {
+                "all" if results.contains(&Some(false)) => Some(false),
+                "all" if results.contains(&None) => None,
+                "all" => Some(true),
+                "any" if results.contains(&Some(true)) => Some(true),
+                "any" if results.contains(&None) => None,
+                "any" => Some(false),
+                _ => match results.as_slice() {
+                    [result] => result.map(|result| !result),
+                    _ => {
+                        return Err(syn::Error::new_spanned(
+                            list,
+                            "cfg(not(...)) expects exactly one predicate",
+                        ));
+                    }
+                },
+            })
+        }
+        Meta::Path(_) => Ok(evaluate_option(&name, None)),
+        Meta::NameValue(name_value) => match &name_value.value {
+            syn::Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Str(value),
+                ..
+            }) if name == "feature" => Ok(Some(feature_enabled(&value.value(), config))),
+            syn::Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Str(value),
+                ..
+            }) => Ok(evaluate_option(&name, Some(&value.value()))),
+            _ => Err(syn::Error::new_spanned(
+                name_value,
+                "expected a string literal as cfg value",
+            )),
+        },
+        // e.g. `version(...)`, the executable might not even accept it
+        Meta::List(list) => {
+            config.warn(format!(
+                "unsupported cfg predicate `{}`, items depending on it are skipped",
+                quote::quote! { #list }
+            ));
+            Ok(Some(false))
+        }
+    }
+}
+
+/// Evaluates a configuration option like `unix` or `target_os = "linux"`.
+fn evaluate_option(name: &str, value: Option<&str>) -> Option<bool> {
+    match (name, value) {
+        // the library is not built for tests or docs when hot-reloading
+        ("test" | "doc" | "doctest", None) => Some(false),
+        _ => {
+            let var = format!("CARGO_CFG_{}", name.to_uppercase());
+            match (std::env::var(var), value) {
+                (Ok(_), None) => Some(true),
+                (Ok(values), Some(value)) => Some(values.split(',').any(|v| v == value)),
+                // `CARGO_CFG_TARGET_OS` is always set for build scripts, if it is
+                // there the configuration was forwarded and `name` is not set
+                (Err(_), _) if std::env::var_os("CARGO_CFG_TARGET_OS").is_some() => Some(false),
+                (Err(_), _) => None,
+            }
+        }
+    }
+}
+
+fn feature_enabled(feature: &str, config: &LibConfig) -> bool {
+    match &config.features {
+        Some(features) => features.iter().any(|f| f == feature),
+        None => {
+            config.warn(format!(
+                "library feature \"{feature}\" is assumed to be off, list the enabled features of the library with `features = [...]`"
+            ));
+            false
+        }
+    }
+}
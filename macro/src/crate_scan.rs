@@ -0,0 +1,480 @@
+use proc_macro2::Span;
+use std::path::{Path, PathBuf};
+use syn::{Error, Ident, LitStr, Result, UseTree, Visibility};
+
+use crate::cfg::{Cfg, LibConfig};
+use crate::util::{FileExports, collect_exports, parse_source_file, resolve_source_path};
+
+/// A module of the library crate read by [`read_crate`].
+pub struct CrateModule {
+    /// `None` for the crate root.
+    pub ident: Option<Ident>,
+    pub vis: Visibility,
+    /// `#[cfg(...)]` of the module if its predicate could not be evaluated.
+    pub cfg: Option<syn::Attribute>,
+    /// The file the module is read from, `None` for inline modules.
+    pub source_file: Option<PathBuf>,
+    /// Path of the module from the crate root.
+    pub path: Vec<Ident>,
+    /// Is the module reachable from outside of the crate?
+    pub is_public: bool,
+    pub exports: FileExports,
+    pub uses: Vec<syn::ItemUse>,
+    /// Names re-exported with `pub use` that are not wrapped by the
+    /// `hot_module`, e.g. types. The mirrored module re-exports them from the
+    /// library module, see [`CrateModule::split_reexports`].
+    pub library_reexports: Vec<syn::ItemUse>,
+    pub children: Vec<CrateModule>,
+}
+
+/// Reads the library crate in directory `crate_dir`, starting from its root
+/// source file (`src/lib.rs` unless configured otherwise in `Cargo.toml`). It
+/// follows `mod` declarations into other files as well as inline modules.
+///
+/// Returns the name of the library crate, its root module and the manifest
+/// file.
+pub fn read_crate(crate_dir: LitStr, config: &LibConfig) -> Result<(Ident, CrateModule, PathBuf)> {
+    let span = crate_dir.span();
+    let crate_dir = resolve_source_path(&crate_dir, "library crate directory", |dir| {
+        dir.join("Cargo.toml").is_file()
+    })?;
+
+    let manifest = crate_dir.join("Cargo.toml");
+    let (lib_name, lib_path) = read_manifest(&manifest, span)?;
+    let root_file = crate_dir.join(lib_path);
+
+    let mut root = read_module(
+        ModuleSource::File(root_file, true),
+        None,
+        Visibility::Public(syn::token::Pub(span)),
+        Vec::new(),
+        true,
+        span,
+        config,
+    )?;
+    let reexports = root.split_reexports(&root);
+    root.apply_reexports(reexports);
+
+    Ok((Ident::new(&lib_name, span), root, manifest))
+}
+
+/// Extracts the library name and the path of the library root file from a
+/// `Cargo.toml` manifest.
+fn read_manifest(manifest: &Path, span: Span) -> Result<(String, String)> {
+    let content = std::fs::read_to_string(manifest)
+        .map_err(|err| Error::new(span, format!("Error reading file {manifest:?}: {err}")))?;
+    let manifest_table = content
+        .parse::<toml::Table>()
+        .map_err(|err| Error::new(span, format!("Error parsing {manifest:?}: {err}")))?;
+
+    let string = |section: &str, key: &str| {
+        manifest_table
+            .get(section)
+            .and_then(|section| section.get(key))
+            .and_then(toml::Value::as_str)
+            .map(str::to_string)
+    };
+
+    let name = string("lib", "name")
+        .or_else(|| string("package", "name"))
+        .ok_or_else(|| {
+            Error::new(
+                span,
+                format!("Could not find the crate name in {manifest:?}"),
+            )
+        })?;
+    Ok((
+        name.replace('-', "_"),
+        string("lib", "path").unwrap_or_else(|| "src/lib.rs".to_string()),
+    ))
+}
+
+enum ModuleSource {
+    /// The source file and whether it "owns" its directory (`lib.rs`,
+    /// `mod.rs`), i.e. whether child modules are found next to it.
+    File(PathBuf, bool),
+    /// Items of an inline module and the directory child module files are
+    /// searched in.
+    Inline(Vec<syn::Item>, PathBuf),
+}
+
+fn read_module(
+    source: ModuleSource,
+    ident: Option<Ident>,
+    vis: Visibility,
+    path: Vec<Ident>,
+    is_public: bool,
+    span: Span,
+    config: &LibConfig,
+) -> Result<CrateModule> {
+    let (items, child_dir, source_file) = match source {
+        ModuleSource::File(file, owns_dir) => {
+            let items = parse_source_file(&file, span)?.items;
+            let dir = file.parent().map(Path::to_path_buf).unwrap_or_default();
+            let child_dir = match (&ident, owns_dir) {
+                (Some(ident), false) => dir.join(ident.to_string()),
+                _ => dir,
+            };
+            (items, child_dir, Some(file))
+        }
+        ModuleSource::Inline(items, child_dir) => (items, child_dir, None),
+    };
+
+    let mut children = Vec::new();
+    let mut uses = Vec::new();
+    let mut other_items = Vec::new();
+
+    for item in items {
+        match item {
+            syn::Item::Mod(item_mod) => {
+                // e.g. test modules are not part of the library build
+                let cfg = Cfg::of_item(&item_mod.attrs, config)?;
+                if matches!(cfg, Cfg::Disabled) {
+                    continue;
+                }
+                let child_path = path
+                    .iter()
+                    .cloned()
+                    .chain(std::iter::once(item_mod.ident.clone()))
+                    .collect();
+                let child_is_public = is_public && matches!(item_mod.vis, Visibility::Public(_));
+                let source = match item_mod.content {
+                    Some((_, items)) => {
+                        ModuleSource::Inline(items, child_dir.join(item_mod.ident.to_string()))
+                    }
+                    None => find_module_file(&item_mod, &child_dir, span)?,
+                };
+                let mut child = read_module(
+                    source,
+                    Some(item_mod.ident),
+                    item_mod.vis,
+                    child_path,
+                    child_is_public,
+                    span,
+                    config,
+                )?;
+                child.cfg = cfg.attr();
+                children.push(child);
+            }
+            syn::Item::Use(item_use) => uses.push(item_use),
+            item => other_items.push(item),
+        }
+    }
+
+    Ok(CrateModule {
+        ident,
+        vis,
+        cfg: None,
+        source_file,
+        path,
+        is_public,
+        exports: collect_exports(other_items, span, config)?,
+        uses,
+        library_reexports: Vec::new(),
+        children,
+    })
+}
+
+/// Finds the file of `mod foo;`: `foo.rs`, `foo/mod.rs` or the file specified
+/// with a `#[path = "..."]` attribute.
+fn find_module_file(item_mod: &syn::ItemMod, dir: &Path, span: Span) -> Result<ModuleSource> {
+    let path_attr = item_mod
+        .attrs
+        .iter()
+        .find(|attr| attr.path().is_ident("path"))
+        .map(|attr| match &attr.meta {
+            syn::Meta::NameValue(syn::MetaNameValue {
+                value:
+                    syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Str(path),
+                        ..
+                    }),
+                ..
+            }) => Ok(path.value()),
+            _ => Err(Error::new(span, "expected #[path = \"...\"]")),
+        })
+        .transpose()?;
+
+    let candidates = match path_attr {
+        Some(path) => vec![(dir.join(path), true)],
+        None => vec![
+            (dir.join(format!("{}.rs", item_mod.ident)), false),
+            (dir.join(item_mod.ident.to_string()).join("mod.rs"), true),
+        ],
+    };
+
+    candidates
+        .iter()
+        .find(|(file, _)| file.exists())
+        .map(|(file, owns_dir)| ModuleSource::File(file.clone(), *owns_dir))
+        .ok_or_else(|| {
+            let tried = candidates
+                .iter()
+                .map(|(file, _)| format!("{file:?}"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            Error::new(
+                span,
+                format!(
+                    "Could not find the file of module {}, tried {tried}",
+                    item_mod.ident
+                ),
+            )
+        })
+}
+
+/// The `use` items of a module and its descendants after
+/// [`CrateModule::split_reexports`].
+pub struct Reexports {
+    uses: Vec<syn::ItemUse>,
+    library: Vec<syn::ItemUse>,
+    children: Vec<Reexports>,
+}
+
+/// How to rewrite the paths of a `use` item of the library so that it can be
+/// used inside the mirrored module of the `hot_module`.
+pub enum UsePaths<'a> {
+    /// Absolute paths into the library crate `.0`.
+    Library(&'a Ident),
+    /// Paths relative to the mirrored modules, `crate::` becomes the root of
+    /// the `hot_module`.
+    Mirror,
+}
+
+impl CrateModule {
+    /// The source files of this module and all its descendants.
+    pub fn source_files(&self) -> Vec<PathBuf> {
+        self.source_file
+            .iter()
+            .cloned()
+            .chain(self.children.iter().flat_map(CrateModule::source_files))
+            .collect()
+    }
+
+    /// The module at `path` relative to this module.
+    fn module(&self, path: &[Ident]) -> Option<&CrateModule> {
+        path.iter().try_fold(self, |module, ident| {
+            module
+                .children
+                .iter()
+                .find(|child| child.ident.as_ref() == Some(ident))
+        })
+    }
+
+    /// Splits the `pub use` items of a reachable module of the library crate
+    /// `root` into single names. Names that lead to a hot function or a module
+    /// are kept as they are mirrored by the `hot_module`. The others, e.g.
+    /// `pub use state::State;` of a private module `state`, can only be
+    /// re-exported from the library module itself.
+    pub fn split_reexports(&self, root: &CrateModule) -> Reexports {
+        let mut uses = Vec::new();
+        let mut library = Vec::new();
+        for item_use in &self.uses {
+            if !self.is_public || !matches!(item_use.vis, Visibility::Public(_)) {
+                uses.push(item_use.clone());
+                continue;
+            }
+            for (prefix, leaf) in use_leaves(&item_use.tree) {
+                let with_tree = |tree| syn::ItemUse {
+                    tree,
+                    ..item_use.clone()
+                };
+                match leaf_names(&leaf) {
+                    Some((target, name))
+                        if !root.leads_to_mirror(&self.path, &prefix, target, 0) =>
+                    {
+                        library.push(with_tree(UseTree::Name(syn::UseName {
+                            ident: name.clone(),
+                        })));
+                    }
+                    _ => uses.push(with_tree(join_use_tree(prefix, leaf))),
+                }
+            }
+        }
+        Reexports {
+            uses,
+            library,
+            children: self
+                .children
+                .iter()
+                .map(|child| child.split_reexports(root))
+                .collect(),
+        }
+    }
+
+    pub fn apply_reexports(&mut self, reexports: Reexports) {
+        self.uses = reexports.uses;
+        self.library_reexports = reexports.library;
+        for (child, reexports) in self.children.iter_mut().zip(reexports.children) {
+            child.apply_reexports(reexports);
+        }
+    }
+
+    /// Does `prefix::ident`, used in the module at `from`, name a hot
+    /// function, a generic hot function or a module of this crate, directly
+    /// or through other `pub use` items?
+    fn leads_to_mirror(
+        &self,
+        from: &[Ident],
+        prefix: &[Ident],
+        ident: &Ident,
+        depth: usize,
+    ) -> bool {
+        // guards against cyclic re-exports
+        if depth > 16 {
+            return false;
+        }
+        let mut path = from.to_vec();
+        for (index, segment) in prefix.iter().enumerate() {
+            match segment.to_string().as_str() {
+                "crate" if index == 0 => path.clear(),
+                "self" if index == 0 => {}
+                "super" => {
+                    if path.pop().is_none() {
+                        return false;
+                    }
+                }
+                // only modules of this crate, other crates are not mirrored
+                _ => {
+                    path.push(segment.clone());
+                    if self.module(&path).is_none() {
+                        return false;
+                    }
+                }
+            }
+        }
+        let Some(module) = self.module(&path) else {
+            return false;
+        };
+        if ident == "self" {
+            return true;
+        }
+
+        let FileExports {
+            functions,
+            generic_functions,
+            ..
+        } = &module.exports;
+        module
+            .children
+            .iter()
+            .any(|child| child.ident.as_ref() == Some(ident))
+            || functions.iter().any(|(f, _)| &f.sig.ident == ident)
+            || generic_functions.iter().any(|(f, _)| &f.sig.ident == ident)
+            || module
+                .uses
+                .iter()
+                .filter(|item_use| matches!(item_use.vis, Visibility::Public(_)))
+                .flat_map(|item_use| use_leaves(&item_use.tree))
+                .any(|(prefix, leaf)| {
+                    matches!(leaf_names(&leaf), Some((target, name))
+                        if name == ident && self.leads_to_mirror(&module.path, &prefix, target, depth + 1))
+                })
+    }
+
+    /// Rewrites a `use` tree of this module. Returns `None` for imports of
+    /// external crates (other than `std`, `core` and `alloc`) as the
+    /// executable might not depend on those.
+    pub fn rewrite_use_tree(&self, tree: &UseTree, paths: UsePaths) -> Option<UseTree> {
+        let UseTree::Path(use_path) = tree else {
+            return None;
+        };
+        let is_child = self
+            .children
+            .iter()
+            .any(|child| child.ident.as_ref() == Some(&use_path.ident));
+
+        let (prefix, rest): (Vec<Ident>, &UseTree) =
+            match (use_path.ident.to_string().as_str(), paths) {
+                ("std" | "core" | "alloc", _) => return Some(tree.clone()),
+                // the mirrored module tree has the same shape as the library
+                ("self" | "super", UsePaths::Mirror) => return Some(tree.clone()),
+                (_, UsePaths::Mirror) if is_child => return Some(tree.clone()),
+                ("crate", UsePaths::Mirror) => match self.path.len() {
+                    0 => (
+                        vec![Ident::new("self", use_path.ident.span())],
+                        &use_path.tree,
+                    ),
+                    depth => (
+                        vec![Ident::new("super", use_path.ident.span()); depth],
+                        &use_path.tree,
+                    ),
+                },
+                ("crate", UsePaths::Library(lib)) => (vec![lib.clone()], &use_path.tree),
+                ("self", UsePaths::Library(lib)) => (
+                    std::iter::once(lib.clone())
+                        .chain(self.path.iter().cloned())
+                        .collect(),
+                    &use_path.tree,
+                ),
+                ("super", UsePaths::Library(lib)) => {
+                    let mut rest = tree;
+                    let mut supers = 0;
+                    while let UseTree::Path(path) = rest
+                        && path.ident == "super"
+                    {
+                        supers += 1;
+                        rest = &path.tree;
+                    }
+                    let parent = self.path.len().checked_sub(supers)?;
+                    (
+                        std::iter::once(lib.clone())
+                            .chain(self.path[..parent].iter().cloned())
+                            .collect(),
+                        rest,
+                    )
+                }
+                (_, UsePaths::Library(lib)) if is_child => (
+                    std::iter::once(lib.clone())
+                        .chain(self.path.iter().cloned())
+                        .collect(),
+                    tree,
+                ),
+                _ => return None,
+            };
+
+        Some(join_use_tree(prefix, rest.clone()))
+    }
+}
+
+/// The single names (or globs) of a `use` tree along with their path.
+fn use_leaves(tree: &UseTree) -> Vec<(Vec<Ident>, UseTree)> {
+    fn collect(tree: &UseTree, prefix: &mut Vec<Ident>, leaves: &mut Vec<(Vec<Ident>, UseTree)>) {
+        match tree {
+            UseTree::Path(path) => {
+                prefix.push(path.ident.clone());
+                collect(&path.tree, prefix, leaves);
+                prefix.pop();
+            }
+            UseTree::Group(group) => {
+                for tree in &group.items {
+                    collect(tree, prefix, leaves);
+                }
+            }
+            leaf => leaves.push((prefix.clone(), leaf.clone())),
+        }
+    }
+    let mut leaves = Vec::new();
+    collect(tree, &mut Vec::new(), &mut leaves);
+    leaves
+}
+
+/// For a single name of a `use` tree, the name it refers to and the name it
+/// is available as. `None` for globs.
+fn leaf_names(leaf: &UseTree) -> Option<(&Ident, &Ident)> {
+    match leaf {
+        UseTree::Name(name) => Some((&name.ident, &name.ident)),
+        UseTree::Rename(rename) => Some((&rename.ident, &rename.rename)),
+        _ => None,
+    }
+}
+
+fn join_use_tree(prefix: Vec<Ident>, leaf: UseTree) -> UseTree {
+    prefix.into_iter().rev().fold(leaf, |tree, ident| {
+        UseTree::Path(syn::UsePath {
+            ident,
+            colon2_token: Default::default(),
+            tree: Box::new(tree),
+        })
+    })
+}
@@ -0,0 +1,162 @@
+use proc_macro2::TokenStream;
+use syn::{ItemFn, Result, Visibility, spanned::Spanned};
+
+use crate::util::{
+    GenericFunction, HotFunctionArgs, WrapperSignature, async_shim_signature, catch_output,
+    catch_symbol_name, unsafe_if, wrapper_signature,
+};
+
+/// Without arguments, `#[hot_function]` just adds `#[unsafe(no_mangle)]`.
+///
+/// For a generic function with an instantiation list like
+///
+/// ```ignore
+/// #[hot_function(instantiate = [u32, f32])]
+/// pub fn double<T: std::ops::Add<Output = T> + Copy>(x: T) -> T { x + x }
+/// ```
+///
+/// the function is kept as is and for every listed type a monomorphized
+/// version is exported:
+///
+/// ```ignore
+/// #[unsafe(no_mangle)]
+/// pub fn __hot_generic__double__u32(__hot_arg0: u32) -> u32 {
+///     double::<u32>(__hot_arg0)
+/// }
+/// ```
+///
+/// An `async fn` is kept as is as well, the library exports a function
+/// returning its future boxed, see [`async_shim_signature`].
+pub(crate) fn export_hot_function(
+    HotFunctionArgs {
+        instantiations,
+        send,
+    }: HotFunctionArgs,
+    mut item_fn: ItemFn,
+) -> Result<TokenStream> {
+    if !matches!(item_fn.vis, Visibility::Public(_)) {
+        return Err(syn::Error::new(
+            item_fn.sig.span(),
+            "#[hot_function] can only be used on public functions",
+        ));
+    }
+
+    if item_fn.sig.asyncness.is_some() {
+        if instantiations.is_some() {
+            return Err(syn::Error::new(
+                item_fn.sig.span(),
+                "async functions cannot have an instantiation list",
+            ));
+        }
+        return export_async_function(item_fn, send.is_some());
+    }
+    if let Some(send) = send {
+        return Err(syn::Error::new(
+            send.span(),
+            "`send` only applies to async functions",
+        ));
+    }
+
+    let Some(instantiations) = instantiations else {
+        let catch_shim = export_catch_shim(&item_fn);
+        item_fn
+            .attrs
+            .push(syn::parse_quote! { #[unsafe(no_mangle)] });
+        return Ok(quote::quote! {
+            #item_fn
+            #catch_shim
+        });
+    };
+
+    let generic = GenericFunction::new(item_fn.sig.clone(), instantiations)?;
+    let ident = &generic.sig.ident;
+    let arg_names = generic.arg_names();
+
+    let exports = generic.instantiations.iter().map(|ty| {
+        let sig = generic.instantiated_sig(ty);
+        let call = unsafe_if(
+            &generic.sig.unsafety,
+            quote::quote! { #ident::<#ty>( #( #arg_names ),* ) },
+        );
+        quote::quote! {
+            #[doc(hidden)]
+            #[allow(non_snake_case)]
+            #[unsafe(no_mangle)]
+            pub #sig {
+                #call
+            }
+        }
+    });
+
+    Ok(quote::quote! {
+        #item_fn
+        #( #exports )*
+    })
+}
+
+/// For `pub fn parse(input: &str) -> Config` exports
+///
+/// ```ignore
+/// #[unsafe(no_mangle)]
+/// pub fn __hot_catch__parse(input: &str) -> Result<Config, Option<String>> {
+///     std::panic::catch_unwind(AssertUnwindSafe(move || parse(input))).map_err(/* message */)
+/// }
+/// ```
+///
+/// A `hot_module` with an `on_panic` policy calls it so that panics are caught
+/// by the standard library of the library. Unless both are linked against the
+/// same `libstd`, the executable cannot catch them. Functions the shim cannot
+/// be generated for are exported as is.
+fn export_catch_shim(item_fn: &ItemFn) -> Option<TokenStream> {
+    let ident = &item_fn.sig.ident;
+    let WrapperSignature {
+        mut sig, arg_names, ..
+    } = wrapper_signature(item_fn.sig.clone()).ok()?;
+    sig.ident = catch_symbol_name(ident);
+    sig.output = catch_output(&sig.output);
+    let call = unsafe_if(&sig.unsafety, quote::quote! { #ident( #( #arg_names ),* ) });
+
+    Some(quote::quote! {
+        #[doc(hidden)]
+        #[allow(non_snake_case)]
+        #[unsafe(no_mangle)]
+        pub #sig {
+            ::std::panic::catch_unwind(::std::panic::AssertUnwindSafe(move || #call)).map_err(|payload| {
+                payload
+                    .downcast_ref::<&str>()
+                    .map(|message| ::std::string::ToString::to_string(message))
+                    .or_else(|| payload.downcast_ref::<::std::string::String>().cloned())
+            })
+        }
+    })
+}
+
+/// For `pub async fn handle(req: Request) -> Response` exports
+///
+/// ```ignore
+/// #[unsafe(no_mangle)]
+/// pub fn __hot_async__handle<'__hot_future>(
+///     req: Request,
+/// ) -> Pin<Box<dyn Future<Output = Response> + '__hot_future>> {
+///     Box::pin(handle(req))
+/// }
+/// ```
+///
+/// With `send` the future is `Send` as well.
+fn export_async_function(item_fn: ItemFn, send: bool) -> Result<TokenStream> {
+    let ident = &item_fn.sig.ident;
+    let WrapperSignature { sig, arg_names, .. } =
+        wrapper_signature(async_shim_signature(&item_fn.sig, send)?)?;
+    let call = unsafe_if(&sig.unsafety, quote::quote! { #ident( #( #arg_names ),* ) });
+
+    Ok(quote::quote! {
+        #item_fn
+
+        #[doc(hidden)]
+        #[allow(non_snake_case)]
+        #[unsafe(no_mangle)]
+        pub #sig {
+            Box::pin(#call)
+        }
+    })
+}
@@ -0,0 +1,181 @@
+use proc_macro2::TokenStream;
+use syn::{Error, ImplItem, ItemImpl, Result, Visibility, spanned::Spanned};
+
+use crate::cfg::cfg_attrs;
+use crate::util::{MethodSignature, method_signature, method_symbol_name, unsafe_if};
+
+/// Attributes of a method that its hidden implementation keeps.
+const IMPL_ATTRS: &[&str] = &["cfg", "allow", "warn", "deny", "forbid", "expect"];
+
+/// For an impl block like
+///
+/// ```ignore
+/// #[hot_methods]
+/// impl Counter {
+///     pub fn value(&self) -> i32 { self.0 }
+/// }
+/// ```
+///
+/// generates
+///
+/// ```ignore
+/// impl Counter {
+///     pub fn value(&self) -> i32 {
+///         if let Some(hook) = __HOT_METHOD_HOOK__Counter__value.get() {
+///             return hook(self);
+///         }
+///         Self::__hot_impl_value(self)
+///     }
+///
+///     fn __hot_impl_value(&self) -> i32 { self.0 }
+///
+///     pub fn __hot_set_value(hook: fn(&Counter) -> i32) { /* ... */ }
+/// }
+///
+/// static __HOT_METHOD_HOOK__Counter__value: OnceLock<fn(&Counter) -> i32> = OnceLock::new();
+///
+/// #[unsafe(no_mangle)]
+/// pub fn __hot_method__Counter__value(__hot_arg0: &Counter) -> i32 {
+///     Counter::__hot_impl_value(__hot_arg0)
+/// }
+/// ```
+///
+/// The exported trampoline is what the `hot_module` calls. The hook is set by
+/// the `hot_module` in the executable so that calling the method on the
+/// statically linked type will dispatch to the newest library version.
+pub(crate) fn export_hot_methods(mut item_impl: ItemImpl) -> Result<TokenStream> {
+    if item_impl.trait_.is_some() {
+        return Err(Error::new(
+            item_impl.span(),
+            "#[hot_methods] can only be used on inherent impl blocks",
+        ));
+    }
+    if !item_impl.generics.params.is_empty() {
+        return Err(Error::new(
+            item_impl.generics.span(),
+            "#[hot_methods] does not support generic impl blocks",
+        ));
+    }
+
+    let self_ty = (*item_impl.self_ty).clone();
+    let mut impl_items = Vec::new();
+    let mut module_items = Vec::new();
+
+    for item in item_impl.items {
+        let method = match item {
+            ImplItem::Fn(method) if matches!(method.vis, Visibility::Public(_)) => method,
+            item => {
+                impl_items.push(item);
+                continue;
+            }
+        };
+
+        let ident = &method.sig.ident;
+        let symbol = method_symbol_name(&self_ty, ident)?;
+        let MethodSignature {
+            arg_names,
+            fn_ptr,
+            free_fn_sig,
+            ..
+        } = method_signature(&self_ty, &method.sig, symbol.clone())?;
+
+        let impl_ident = syn::Ident::new(&format!("__hot_impl_{ident}"), ident.span());
+        let set_ident = syn::Ident::new(&format!("__hot_set_{ident}"), ident.span());
+        let hook_ident = syn::Ident::new(
+            &format!(
+                "__HOT_METHOD_HOOK{}",
+                symbol.to_string().trim_start_matches("__hot_method")
+            ),
+            ident.span(),
+        );
+        let unsafety = method.sig.unsafety;
+        // everything generated for a method is gone if it is configured away
+        let cfg = cfg_attrs(&method.attrs).cloned().collect::<Vec<_>>();
+
+        // the public method keeps its signature but gets plain argument names
+        let mut public_method = method.clone();
+        for (arg, name) in public_method.sig.inputs.iter_mut().zip(&arg_names) {
+            match arg {
+                // `mut self` is not needed to pass self on
+                syn::FnArg::Receiver(receiver) if receiver.reference.is_none() => {
+                    receiver.mutability = None
+                }
+                syn::FnArg::Receiver(_) => {}
+                syn::FnArg::Typed(typed) => typed.pat = syn::parse_quote! { #name },
+            }
+        }
+        let call_args = method
+            .sig
+            .inputs
+            .iter()
+            .zip(&arg_names)
+            .map(|(arg, name)| match arg {
+                syn::FnArg::Receiver(_) => quote::quote! { self },
+                syn::FnArg::Typed(_) => quote::quote! { #name },
+            })
+            .collect::<Vec<_>>();
+        let hook_call = unsafe_if(&unsafety, quote::quote! { hook( #( #call_args ),* ) });
+        let impl_call = unsafe_if(
+            &unsafety,
+            quote::quote! { Self::#impl_ident( #( #call_args ),* ) },
+        );
+        public_method.block = syn::parse_quote! {
+            {
+                if let Some(hook) = #hook_ident.get() {
+                    return #hook_call;
+                }
+                #impl_call
+            }
+        };
+
+        // the original method body, called by the trampoline and as fallback
+        let mut impl_method = method;
+        impl_method.sig.ident = impl_ident.clone();
+        impl_method.vis = Visibility::Inherited;
+        impl_method.attrs = impl_method
+            .attrs
+            .into_iter()
+            .filter(|attr| IMPL_ATTRS.iter().any(|name| attr.path().is_ident(name)))
+            .chain([
+                syn::parse_quote! { #[doc(hidden)] },
+                syn::parse_quote! { #[inline(always)] },
+            ])
+            .collect();
+
+        impl_items.push(ImplItem::Fn(public_method));
+        impl_items.push(ImplItem::Fn(impl_method));
+        impl_items.push(syn::parse_quote! {
+            #( #cfg )*
+            #[doc(hidden)]
+            pub fn #set_ident(hook: #fn_ptr) {
+                let _ = #hook_ident.set(hook);
+            }
+        });
+
+        let trampoline_call = unsafe_if(
+            &unsafety,
+            quote::quote! { <#self_ty>::#impl_ident( #( #arg_names ),* ) },
+        );
+        module_items.push(quote::quote! {
+            #( #cfg )*
+            #[doc(hidden)]
+            #[allow(non_upper_case_globals)]
+            static #hook_ident: ::std::sync::OnceLock<#fn_ptr> = ::std::sync::OnceLock::new();
+
+            #( #cfg )*
+            #[doc(hidden)]
+            #[allow(non_snake_case)]
+            #[unsafe(no_mangle)]
+            pub #free_fn_sig {
+                #trampoline_call
+            }
+        });
+    }
+
+    item_impl.items = impl_items;
+
+    Ok(quote::quote! {
+        #item_impl
+        #( #module_items )*
+    })
+}
@@ -0,0 +1,8 @@
+//! Attributes used inside the hot-reloadable library to export items that
+//! cannot be exported with a plain `#[unsafe(no_mangle)]`.
+
+mod functions;
+mod methods;
+
+pub(crate) use functions::export_hot_function;
+pub(crate) use methods::export_hot_methods;
@@ -8,6 +8,40 @@ pub(crate) struct HotModuleAttribute {
     pub(crate) file_watch_debounce_ms: syn::LitInt,
     pub(crate) crate_name: syn::Path,
     pub(crate) loaded_lib_name_template: syn::Expr,
+    /// `static_fallback = "lib"` and the predicate of `enable = cfg(...)`
+    pub(crate) static_fallback: Option<(syn::Path, proc_macro2::TokenStream)>,
+    /// `leak_max_versions = 8`, enables the leak mode
+    pub(crate) leak_max_versions: Option<syn::LitInt>,
+    /// `leak_max_bytes = 500_000_000`, enables the leak mode
+    pub(crate) leak_max_bytes: Option<syn::LitInt>,
+    /// `tls_destructors = "never_unload"`, the `TlsDestructorPolicy` variant
+    pub(crate) tls_destructor_policy: Option<Ident>,
+    /// `on_panic = "rollback"`, panics of hot functions are caught
+    pub(crate) on_panic: Option<PanicPolicy>,
+    /// `probe = true` or `probe_timeout_ms = 5000`, new library versions are
+    /// probed in a subprocess with this timeout
+    pub(crate) probe_timeout_ms: Option<syn::LitInt>,
+}
+
+/// What the hot function wrappers do when the library function panics. With
+/// every policy subscribers are informed about the panic.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PanicPolicy {
+    /// Continue unwinding into the caller.
+    Propagate,
+    /// Like `Propagate` but also generate `try_` wrappers that return the
+    /// panic as an error.
+    Try,
+    /// Like `Try` but also roll back to the library version that was loaded
+    /// before.
+    Rollback,
+}
+
+impl PanicPolicy {
+    /// Are `try_` wrappers generated?
+    pub(crate) fn try_wrappers(self) -> bool {
+        matches!(self, Self::Try | Self::Rollback)
+    }
 }
 
 // Parses something like `#[hot(name = "lib")]`.
@@ -18,6 +52,14 @@ impl syn::parse::Parse for HotModuleAttribute {
         let mut file_watch_debounce_ms = None;
         let mut crate_name = None;
         let mut loaded_lib_name_template = None;
+        let mut static_fallback = None;
+        let mut enable = None;
+        let mut leak_max_versions = None;
+        let mut leak_max_bytes = None;
+        let mut tls_destructor_policy = None;
+        let mut on_panic = None;
+        let mut probe = None;
+        let mut probe_timeout_ms = None;
 
         let args = Punctuated::<syn::Expr, token::Comma>::parse_separated_nonempty(stream)?;
 
@@ -43,6 +85,74 @@ impl syn::parse::Parse for HotModuleAttribute {
                         continue;
                     }
 
+                    syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Int(lit),
+                        ..
+                    }) if expr_is_ident(&left, "leak_max_versions") => {
+                        leak_max_versions = Some(lit);
+                        continue;
+                    }
+
+                    syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Int(lit),
+                        ..
+                    }) if expr_is_ident(&left, "leak_max_bytes") => {
+                        leak_max_bytes = Some(lit);
+                        continue;
+                    }
+
+                    syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Bool(lit),
+                        ..
+                    }) if expr_is_ident(&left, "probe") => {
+                        probe = Some(lit);
+                        continue;
+                    }
+
+                    syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Int(lit),
+                        ..
+                    }) if expr_is_ident(&left, "probe_timeout_ms") => {
+                        probe_timeout_ms = Some(lit);
+                        continue;
+                    }
+
+                    syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Str(s),
+                        ..
+                    }) if expr_is_ident(&left, "tls_destructors") => {
+                        let variant = match s.value().as_str() {
+                            "unload" => "Unload",
+                            "never_unload" => "NeverUnload",
+                            _ => {
+                                return Err(Error::new(
+                                    s.span(),
+                                    r#"expected "unload" or "never_unload""#,
+                                ));
+                            }
+                        };
+                        tls_destructor_policy = Some(Ident::new(variant, s.span()));
+                        continue;
+                    }
+
+                    syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Str(s),
+                        ..
+                    }) if expr_is_ident(&left, "on_panic") => {
+                        on_panic = Some(match s.value().as_str() {
+                            "propagate" => PanicPolicy::Propagate,
+                            "try" => PanicPolicy::Try,
+                            "rollback" => PanicPolicy::Rollback,
+                            _ => {
+                                return Err(Error::new(
+                                    s.span(),
+                                    r#"expected "propagate", "try" or "rollback""#,
+                                ));
+                            }
+                        });
+                        continue;
+                    }
+
                     expr if expr_is_ident(&left, "dylib") => {
                         lib_name = Some(expr);
                         continue;
@@ -71,6 +181,32 @@ impl syn::parse::Parse for HotModuleAttribute {
                         continue;
                     }
 
+                    syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Str(s),
+                        ..
+                    }) if expr_is_ident(&left, "static_fallback") => {
+                        let mut path = s.parse::<syn::Path>()?;
+                        let first = &path.segments[0].ident;
+                        if first != "crate" && first != "self" && first != "super" {
+                            path.leading_colon = Some(Default::default());
+                        }
+                        static_fallback = Some((path, s.span()));
+                        continue;
+                    }
+
+                    syn::Expr::Call(syn::ExprCall { func, args, .. })
+                        if expr_is_ident(&left, "enable") && expr_is_ident(&func, "cfg") =>
+                    {
+                        if args.len() != 1 {
+                            return Err(Error::new(
+                                args.span(),
+                                "expected a single predicate like `cfg(feature = \"reload\")`",
+                            ));
+                        }
+                        enable = Some((quote::ToTokens::to_token_stream(&args), left.span()));
+                        continue;
+                    }
+
                     _ => return Err(Error::new(left.span(), "unexpected attribute name")),
                 },
 
@@ -116,12 +252,42 @@ impl syn::parse::Parse for HotModuleAttribute {
             }
         };
 
+        let static_fallback = match (static_fallback, enable) {
+            (Some((path, _)), Some((predicate, _))) => Some((path, predicate)),
+            (None, None) => None,
+            (Some((_, span)), None) | (None, Some((_, span))) => {
+                return Err(Error::new(
+                    span,
+                    "`static_fallback` and `enable` need to be used together",
+                ));
+            }
+        };
+
+        match (probe.map(|probe| probe.value()), &probe_timeout_ms) {
+            (Some(true), None) => {
+                probe_timeout_ms = Some(LitInt::new("5000", stream.span()));
+            }
+            (Some(false), Some(timeout)) => {
+                return Err(Error::new(
+                    timeout.span(),
+                    "`probe_timeout_ms` enables the probe, it cannot be used with `probe = false`",
+                ));
+            }
+            _ => {}
+        }
+
         Ok(HotModuleAttribute {
             lib_name,
             lib_dir,
             file_watch_debounce_ms,
             crate_name,
             loaded_lib_name_template,
+            static_fallback,
+            leak_max_versions,
+            leak_max_bytes,
+            tls_destructor_policy,
+            on_panic,
+            probe_timeout_ms,
         })
     }
 }
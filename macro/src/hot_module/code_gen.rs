@@ -1,58 +1,193 @@
 use proc_macro2::Span;
-use syn::{Expr, FnArg, ItemFn, LitByteStr, LitStr, Path, Result, Visibility, token};
-use syn::{ForeignItemFn, LitInt};
+use syn::{FnArg, ItemFn, LitByteStr, LitStr, Path, Result, Visibility, token};
+use syn::{ForeignItemFn, ImplItem, Item, ItemImpl};
 
-use crate::util::ident_from_pat;
+use super::HotModuleAttribute;
+use super::attribute::PanicPolicy;
+use crate::cfg::cfg_attrs;
+use crate::crate_scan::{CrateModule, UsePaths};
+use crate::util::{
+    FileExports, GenericFunction, MethodSignature, WrapperSignature, arg_name,
+    async_shim_signature, async_symbol_name, async_wrapper_signature, catch_output,
+    catch_symbol_name, forwarded_attrs, hot_function_args, method_signature, method_symbol_name,
+    unsafe_if, wrapper_signature,
+};
 
 pub(crate) fn generate_lib_loader_items(
-    lib_dir: &Expr,
-    lib_name: &Expr,
-    file_watch_debounce_ms: &LitInt,
-    crate_name: &Path,
-    loaded_lib_name_template: &Expr,
+    module_ident: &syn::Ident,
+    args: &HotModuleAttribute,
+    method_hooks: &[proc_macro2::TokenStream],
+    probe_symbols: &[proc_macro2::TokenStream],
     span: Span,
 ) -> Result<proc_macro2::TokenStream> {
-    let result = quote::quote_spanned! {span=>
-        static mut LIB_CHANGE_NOTIFIER: Option<::std::sync::Arc<::std::sync::RwLock<#crate_name::LibReloadNotifier>>> = None;
-        static LIB_CHANGE_NOTIFIER_INIT: ::std::sync::Once = ::std::sync::Once::new();
+    let HotModuleAttribute {
+        lib_dir,
+        lib_name,
+        file_watch_debounce_ms,
+        crate_name,
+        loaded_lib_name_template,
+        leak_max_versions,
+        leak_max_bytes,
+        tls_destructor_policy,
+        on_panic,
+        probe_timeout_ms,
+        ..
+    } = args;
 
-        fn __lib_notifier() -> ::std::sync::Arc<::std::sync::RwLock<#crate_name::LibReloadNotifier>> {
-            LIB_CHANGE_NOTIFIER_INIT.call_once(|| {
-                let notifier = ::std::sync::Arc::new(::std::sync::RwLock::new(Default::default()));
-                // Safety: guarded by Once, will only be called one time.
-                unsafe {
-                    use ::std::borrow::BorrowMut;
-                    *LIB_CHANGE_NOTIFIER.borrow_mut() = Some(notifier);
+    // e.g. HOT_LIB_DIR_HOT_LIB for `mod hot_lib`
+    let module_name = module_ident.to_string().to_uppercase();
+    let lib_dir_var = LitStr::new(&format!("HOT_LIB_DIR_{module_name}"), span);
+    let lib_name_var = LitStr::new(&format!("HOT_LIB_NAME_{module_name}"), span);
+    let notifier = gen_lib_notifier_items(crate_name, span);
+
+    // old library versions are kept loaded up to the given limits
+    let option = |lit: &Option<syn::LitInt>| match lit {
+        Some(lit) => quote::quote! { Some(#lit) },
+        None => quote::quote! { None },
+    };
+    let leak_limit = (leak_max_versions.is_some() || leak_max_bytes.is_some()).then(|| {
+        let max_versions = option(leak_max_versions);
+        let max_bytes = option(leak_max_bytes);
+        quote::quote_spanned! {span=>
+            lib_loader.set_leak_limit(Some(#crate_name::LeakLimit {
+                max_versions: #max_versions,
+                max_bytes: #max_bytes,
+            }));
+        }
+    });
+
+    let tls_destructor_policy = tls_destructor_policy.as_ref().map(|policy| {
+        quote::quote_spanned! {span=>
+            lib_loader.set_tls_destructor_policy(#crate_name::TlsDestructorPolicy::#policy);
+        }
+    });
+
+    // new library versions are loaded in a subprocess first, it looks up the
+    // symbols of the hot functions
+    let probe = probe_timeout_ms.as_ref().map(|timeout_ms| {
+        quote::quote_spanned! {span=>
+            let mut probe_symbols: ::std::vec::Vec<&str> = ::std::vec::Vec::new();
+            #( #probe_symbols )*
+            lib_loader.set_probe(Some(#crate_name::LibProbe {
+                symbols: probe_symbols.into_iter().map(::std::string::ToString::to_string).collect(),
+                timeout: Some(::std::time::Duration::from_millis(#timeout_ms)),
+                ..Default::default()
+            }));
+        }
+    });
+
+    let call = gen_lib_call_items(crate_name, *on_panic, span);
+    let rollback_enabled = (*on_panic == Some(PanicPolicy::Rollback)).then(|| {
+        quote::quote_spanned! {span=>
+            lib_loader.set_rollback_enabled(true);
+        }
+    });
+
+    // The hooks are installed before `main` runs so that a method called
+    // before any hot function already dispatches to the library. On targets
+    // without an init section they are installed with the lib loader.
+    let method_hooks_init = (!method_hooks.is_empty()).then(|| {
+        quote::quote_spanned! {span=>
+            #[used]
+            #[cfg_attr(
+                any(target_os = "linux", target_os = "android", target_os = "freebsd", target_os = "netbsd", target_os = "openbsd"),
+                unsafe(link_section = ".init_array")
+            )]
+            #[cfg_attr(target_vendor = "apple", unsafe(link_section = "__DATA,__mod_init_func"))]
+            #[cfg_attr(windows, unsafe(link_section = ".CRT$XCU"))]
+            static __LIB_METHOD_HOOKS_INIT: extern "C" fn() = {
+                extern "C" fn install() {
+                    __lib_install_method_hooks();
                 }
-            });
+                install
+            };
+        }
+    });
 
-            // Safety: Once runs before and initializes the global
-            unsafe { LIB_CHANGE_NOTIFIER.as_ref().cloned().unwrap() }
+    let result = quote::quote_spanned! {span=>
+        #notifier
+
+        #call
+
+        // let methods of the statically linked types dispatch to the library
+        fn __lib_install_method_hooks() {
+            #( #method_hooks )*
         }
 
+        #method_hooks_init
+
         fn __lib_loader_subscription() -> #crate_name::LibReloadObserver {
+            __lib_loader_subscription_with(Default::default())
+        }
+
+        fn __lib_loader_subscription_with(options: #crate_name::SubscriptionOptions) -> #crate_name::LibReloadObserver {
             // Make sure that LIB_LOADER_INIT ran and the change messages are
             // live, otherwise we would not get lib updates if none of the hot
             // functions are called.
             let _ = __lib_loader();
-            __lib_notifier()
-                .write()
-                .expect("write lock notifier")
-                .subscribe()
+            __lib_notifier().subscribe_with(options)
         }
 
-        static mut LIB_LOADER: Option<::std::sync::Arc<::std::sync::RwLock<#crate_name::LibReloader>>> = None;
-        static LIB_LOADER_INIT: ::std::sync::Once = ::std::sync::Once::new();
+        fn __lib_update_cursor() -> #crate_name::UpdateCursor {
+            // see __lib_loader_subscription_with
+            let _ = __lib_loader();
+            __lib_notifier().update_cursor()
+        }
+
+        static LIB_LOADER: ::std::sync::OnceLock<::std::sync::Arc<::std::sync::RwLock<#crate_name::LibReloader>>> = ::std::sync::OnceLock::new();
+        static LIB_LOADER_INIT: ::std::sync::Mutex<()> = ::std::sync::Mutex::new(());
 
-        // version counter that counts the reloads
-        static VERSION: ::std::sync::atomic::AtomicUsize = ::std::sync::atomic::AtomicUsize::new(0);
         // for simple queries
         static WAS_UPDATED: ::std::sync::atomic::AtomicBool = ::std::sync::atomic::AtomicBool::new(false);
 
         fn __lib_loader() -> ::std::sync::Arc<::std::sync::RwLock<#crate_name::LibReloader>> {
-            LIB_LOADER_INIT.call_once(|| {
-                let mut lib_loader = #crate_name::LibReloader::new(#lib_dir, #lib_name, Some(::std::time::Duration::from_millis(#file_watch_debounce_ms)), #loaded_lib_name_template)
-                    .expect("failed to create hot reload loader");
+            __lib_loader_init().expect("failed to create hot reload loader")
+        }
+
+        fn __lib_info() -> #crate_name::LibInfo {
+            __lib_loader().read().expect("lib loader RwLock read failed").info()
+        }
+
+        fn __lib_generation_guard() -> ::std::option::Option<#crate_name::GenerationGuard> {
+            __lib_loader().read().expect("lib loader RwLock read failed").generation_guard()
+        }
+
+        /// Creates the lib loader and starts the update thread unless that
+        /// already happened. Can be retried after an error.
+        fn __lib_loader_init() -> ::std::result::Result<::std::sync::Arc<::std::sync::RwLock<#crate_name::LibReloader>>, #crate_name::HotReloaderError> {
+            if let Some(lib_loader) = LIB_LOADER.get() {
+                return Ok(lib_loader.clone());
+            }
+            let _init_guard = LIB_LOADER_INIT.lock().unwrap_or_else(::std::sync::PoisonError::into_inner);
+            if let Some(lib_loader) = LIB_LOADER.get() {
+                return Ok(lib_loader.clone());
+            }
+
+            {
+                // the library location can be overridden when running the executable
+                let lib_dir = match ::std::env::var_os(#lib_dir_var) {
+                    Some(lib_dir) => {
+                        #crate_name::LibReloader::log_info(&format!("{}={lib_dir:?} overrides the library directory", #lib_dir_var));
+                        ::std::path::PathBuf::from(lib_dir)
+                    }
+                    None => ::std::path::PathBuf::from(#lib_dir),
+                };
+                let lib_name = match ::std::env::var(#lib_name_var) {
+                    Ok(lib_name) => {
+                        #crate_name::LibReloader::log_info(&format!("{}={lib_name:?} overrides the library name", #lib_name_var));
+                        lib_name
+                    }
+                    Err(_) => ::std::string::ToString::to_string(&#lib_name),
+                };
+                #crate_name::LibReloader::log_info(&format!("loading library {lib_name:?} from {lib_dir:?}"));
+
+                let mut lib_loader = #crate_name::LibReloader::new(lib_dir, lib_name, Some(::std::time::Duration::from_millis(#file_watch_debounce_ms)), #loaded_lib_name_template)?;
+                #leak_limit
+                #tls_destructor_policy
+                #rollback_enabled
+                #probe
+
+                __lib_install_method_hooks();
 
                 let change_rx = lib_loader.subscribe_to_file_changes();
                 let lib_loader = ::std::sync::Arc::new(::std::sync::RwLock::new(lib_loader));
@@ -62,65 +197,368 @@ pub(crate) fn generate_lib_loader_items(
                 let _thread = ::std::thread::spawn(move || {
                     loop {
                         if let Ok(()) = change_rx.recv() {
-                            // inform subscribers about about-to-reload
-                            __lib_notifier()
-                                .read()
-                                .expect("read lock notifier")
-                                .send_about_to_reload_event_and_wait_for_blocks();
+                            // inform subscribers about about-to-reload, they might cancel it
+                            if let Some(reason) = __lib_notifier().send_about_to_reload_event_and_wait_for_blocks() {
+                                #crate_name::LibReloader::log_info(&format!("reload cancelled: {reason}"));
+                                lib_loader_for_update
+                                    .read()
+                                    .expect("lib loader RwLock read failed")
+                                    .discard_change();
+                                __lib_notifier().send_reload_cancelled_event(reason);
+                                continue;
+                            }
 
                             // get lock to lib_loader, make sure to not deadlock on it here
                             let mut first_lock_attempt = None;
-                            loop {
+                            let update = loop {
                                 if let Ok(mut lib_loader) = lib_loader_for_update.try_write() {
                                     if let Some(first_lock_attempt) = first_lock_attempt {
                                         let duration: ::std::time::Duration = first_lock_attempt - ::std::time::Instant::now();
                                         #crate_name::LibReloader::log_info(&format!("...got write lock after {}ms!", duration.as_millis()));
                                     }
-                                    let _ = !lib_loader.update().expect("hot lib update()");
-                                    break;
+                                    break lib_loader.update();
                                 }
                                 if first_lock_attempt.is_none() {
                                     first_lock_attempt = Some(::std::time::Instant::now());
                                     #crate_name::LibReloader::log_info("trying to get a write lock...");
                                 }
                                 ::std::thread::sleep(::std::time::Duration::from_millis(1));
+                            };
+
+                            // the error is kept in the lib loader, see `LibReloader::info`
+                            if let Err(err) = update {
+                                #crate_name::LibReloader::log_info(&format!("failed to reload the library: {err}"));
+                                continue;
                             }
 
-                            VERSION.fetch_add(1, ::std::sync::atomic::Ordering::Release);
                             WAS_UPDATED.store(true, ::std::sync::atomic::Ordering::Release);
 
-                            // inform subscribers about lib reloaded
-                            __lib_notifier()
-                                .read()
-                                .expect("read lock notifier")
-                                .send_reloaded_event();
+                            // bump the version and inform subscribers about lib reloaded
+                            __lib_notifier().send_reloaded_event();
                         }
                     }
                 });
 
+                // only one thread gets here, guarded by LIB_LOADER_INIT
+                let _ = LIB_LOADER.set(lib_loader.clone());
+                Ok(lib_loader)
+            }
+        }
+
+    };
+
+    Ok(result)
+}
+
+/// `__lib_call` runs the body of a hot function wrapper while holding the read
+/// lock of the lib loader. With an `on_panic` policy it catches a panic of the
+/// library function, informs subscribers, rolls back with `rollback` and then
+/// continues unwinding. The `try_` wrappers use `__lib_try` to turn that panic
+/// into an error.
+fn gen_lib_call_items(
+    crate_name: &Path,
+    on_panic: Option<PanicPolicy>,
+    span: Span,
+) -> proc_macro2::TokenStream {
+    let Some(on_panic) = on_panic else {
+        return quote::quote_spanned! {span=>
+            fn __lib_call<R>(_function: &'static str, call: impl FnOnce(&#crate_name::LibReloader) -> R) -> R {
+                let lib_loader = __lib_loader();
+                let lib_loader = lib_loader.read().expect("lib loader RwLock read failed");
+                call(&lib_loader)
+            }
+        };
+    };
+
+    let (rollback, rollback_items) = match on_panic {
+        PanicPolicy::Rollback => (
+            Some(quote::quote_spanned! {span=>
+                __lib_rollback(&lib_loader, panic.version);
+            }),
+            Some(gen_lib_rollback_items(crate_name, span)),
+        ),
+        PanicPolicy::Propagate | PanicPolicy::Try => (None, None),
+    };
+
+    quote::quote_spanned! {span=>
+        ::std::thread_local! {
+            // the panic of the last hot function call of this thread
+            static LAST_PANIC: ::std::cell::RefCell<Option<#crate_name::HotPanic>> = const { ::std::cell::RefCell::new(None) };
+        }
+
+        fn __lib_call<R>(function: &'static str, call: impl FnOnce(&#crate_name::LibReloader) -> R) -> R {
+            let lib_loader = __lib_loader();
+            let (result, version) = {
+                let lib_loader = lib_loader.read().expect("lib loader RwLock read failed");
+                let result = ::std::panic::catch_unwind(::std::panic::AssertUnwindSafe(|| call(&lib_loader)));
+                (result, lib_loader.generation())
+            };
+            match result {
+                Ok(result) => result,
+                Err(payload) => {
+                    let panic = #crate_name::HotPanic::new(function, &*payload, version);
+                    #crate_name::LibReloader::log_info(&panic);
+                    #rollback
+                    LAST_PANIC.with(|last| *last.borrow_mut() = Some(panic.clone()));
+                    __lib_notifier().send_panic_event(panic);
+                    ::std::panic::resume_unwind(payload)
+                }
+            }
+        }
+
+        /// Continues unwinding with a panic that the library caught, its
+        /// panic hook already reported it.
+        #[allow(dead_code)]
+        fn __lib_resume_panic(message: Option<String>) -> ! {
+            match message {
+                Some(message) => ::std::panic::resume_unwind(Box::new(message)),
+                None => ::std::panic::resume_unwind(Box::new(())),
+            }
+        }
+
+        #[allow(dead_code)]
+        fn __lib_try<R>(function: &'static str, call: impl FnOnce() -> R) -> ::std::result::Result<R, #crate_name::HotPanic> {
+            ::std::panic::catch_unwind(::std::panic::AssertUnwindSafe(call)).map_err(|payload| {
+                LAST_PANIC
+                    .with(|last| last.borrow_mut().take())
+                    .unwrap_or_else(|| #crate_name::HotPanic::new(function, &*payload, 0))
+            })
+        }
+
+        #rollback_items
+    }
+}
+
+/// `__lib_rollback` restores the library version that was loaded before a
+/// panicking one.
+fn gen_lib_rollback_items(crate_name: &Path, span: Span) -> proc_macro2::TokenStream {
+    quote::quote_spanned! {span=>
+        fn __lib_rollback(lib_loader: &::std::sync::Arc<::std::sync::RwLock<#crate_name::LibReloader>>, version: usize) {
+            fn rollback(lib_loader: &mut #crate_name::LibReloader, version: usize) {
+                // a reload might have replaced the version in the meantime
+                if lib_loader.generation() != version {
+                    return;
+                }
+                match lib_loader.rollback() {
+                    Ok(true) => {
+                        WAS_UPDATED.store(true, ::std::sync::atomic::Ordering::Release);
+                        __lib_notifier().send_reloaded_event();
+                    }
+                    Ok(false) => #crate_name::LibReloader::log_info("no library version to roll back to"),
+                    Err(err) => #crate_name::LibReloader::log_info(&format!("failed to roll back the library: {err}")),
+                }
+            }
+
+            // outer hot function calls hold the read lock if the library
+            // called back into the executable
+            match lib_loader.try_write() {
+                Ok(mut lib_loader) => rollback(&mut lib_loader, version),
+                Err(_) => {
+                    let lib_loader = lib_loader.clone();
+                    ::std::thread::spawn(move || {
+                        rollback(&mut lib_loader.write().expect("lib loader RwLock write failed"), version)
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// The notifier that informs subscribers about library reloads.
+fn gen_lib_notifier_items(crate_name: &Path, span: Span) -> proc_macro2::TokenStream {
+    quote::quote_spanned! {span=>
+        static mut LIB_CHANGE_NOTIFIER: Option<::std::sync::Arc<#crate_name::LibReloadNotifier>> = None;
+        static LIB_CHANGE_NOTIFIER_INIT: ::std::sync::Once = ::std::sync::Once::new();
+
+        fn __lib_notifier() -> ::std::sync::Arc<#crate_name::LibReloadNotifier> {
+            LIB_CHANGE_NOTIFIER_INIT.call_once(|| {
+                let notifier = ::std::sync::Arc::new(Default::default());
                 // Safety: guarded by Once, will only be called one time.
                 unsafe {
                     use ::std::borrow::BorrowMut;
-                    *LIB_LOADER.borrow_mut() = Some(lib_loader);
+                    *LIB_CHANGE_NOTIFIER.borrow_mut() = Some(notifier);
                 }
             });
 
             // Safety: Once runs before and initializes the global
-            unsafe { LIB_LOADER.as_ref().cloned().unwrap() }
+            unsafe { LIB_CHANGE_NOTIFIER.as_ref().cloned().unwrap() }
         }
+    }
+}
 
-    };
+// -=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-
 
-    Ok(result)
+/// An item of the module that is generated instead of the hot module when
+/// reloading is disabled, see `static_fallback` of the `hot_module` attribute.
+pub(crate) enum FallbackItem {
+    /// Import of an item of the statically linked library, `path` is relative
+    /// to the library root.
+    Use {
+        attrs: Vec<syn::Attribute>,
+        vis: Visibility,
+        path: Vec<syn::Ident>,
+        glob: bool,
+    },
+    /// A `#[hot_static]` declaration.
+    Static(syn::ForeignItemStatic),
+    /// A `#[hot_trait]` constructor.
+    Trait(Box<HotTraitConstructor>),
+    /// Anything else is used as is.
+    Item(Box<Item>),
+}
+
+/// Generates the contents of the module that uses the statically linked
+/// library `lib` instead of reloading it. Functions are re-exported from the
+/// library, `#[hot_static]` accessors return a `HotStatic` without a guard and
+/// the reload notifications never happen.
+pub(crate) fn gen_static_fallback_items(
+    lib: &Path,
+    items: &[FallbackItem],
+    crate_name: &Path,
+    span: Span,
+) -> proc_macro2::TokenStream {
+    let items = items.iter().map(|item| match item {
+        FallbackItem::Use {
+            attrs,
+            vis,
+            path,
+            glob,
+        } => {
+            let glob = glob.then(|| quote::quote! { ::* });
+            quote::quote_spanned! {span=>
+                #( #attrs )*
+                #[allow(unused_imports)]
+                #vis use #lib #( ::#path )* #glob;
+            }
+        }
+        FallbackItem::Static(item_static) => {
+            let syn::ForeignItemStatic {
+                attrs,
+                vis,
+                ident,
+                ty,
+                ..
+            } = item_static;
+            let attrs = forwarded_attrs(attrs);
+            quote::quote_spanned! {span=>
+                #( #attrs )*
+                #[allow(non_snake_case)]
+                #vis fn #ident() -> #crate_name::HotStatic<#ty> {
+                    #crate_name::HotStatic::from(&#lib::#ident)
+                }
+            }
+        }
+        FallbackItem::Trait(ctor) => {
+            let HotTraitConstructor {
+                attrs,
+                vis,
+                sig,
+                state,
+                ..
+            } = &**ctor;
+            let ident = &sig.ident;
+            quote::quote_spanned! {span=>
+                #( #attrs )*
+                #vis #sig {
+                    #crate_name::HotDyn::from(#lib::#ident(#state))
+                }
+            }
+        }
+        // imports for the signatures of the wrappers might be unused now
+        FallbackItem::Item(item) if matches!(**item, Item::Use(_)) => quote::quote! {
+            #[allow(unused_imports)]
+            #item
+        },
+        FallbackItem::Item(item) => quote::quote! { #item },
+    });
+    let notifier = gen_lib_notifier_items(crate_name, span);
+
+    quote::quote_spanned! {span=>
+        #( #items )*
+
+        // the library is never reloaded, subscribers wait forever and the
+        // version stays at 0
+        #notifier
+
+        #[allow(dead_code)]
+        fn __lib_loader_subscription() -> #crate_name::LibReloadObserver {
+            __lib_notifier().subscribe()
+        }
+
+        #[allow(dead_code)]
+        fn __lib_loader_subscription_with(options: #crate_name::SubscriptionOptions) -> #crate_name::LibReloadObserver {
+            __lib_notifier().subscribe_with(options)
+        }
+
+        #[allow(dead_code)]
+        fn __lib_update_cursor() -> #crate_name::UpdateCursor {
+            __lib_notifier().update_cursor()
+        }
+
+        #[allow(dead_code)]
+        static WAS_UPDATED: ::std::sync::atomic::AtomicBool = ::std::sync::atomic::AtomicBool::new(false);
+
+        #[allow(dead_code)]
+        fn __lib_loader_init() -> ::std::result::Result<(), #crate_name::HotReloaderError> {
+            Ok(())
+        }
+
+        #[allow(dead_code)]
+        fn __lib_info() -> #crate_name::LibInfo {
+            Default::default()
+        }
+
+        // the statically linked library has no generations
+        #[allow(dead_code)]
+        fn __lib_generation_guard() -> ::std::option::Option<#crate_name::GenerationGuard> {
+            None
+        }
+
+        #[allow(dead_code)]
+        fn __lib_try<R>(function: &'static str, call: impl FnOnce() -> R) -> ::std::result::Result<R, #crate_name::HotPanic> {
+            ::std::panic::catch_unwind(::std::panic::AssertUnwindSafe(call))
+                .map_err(|payload| #crate_name::HotPanic::new(function, &*payload, 0))
+        }
+    }
 }
 
 // -=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-
 
 pub(crate) fn gen_hot_module_function_for(
     lib_function: ForeignItemFn,
+    catch_panics: bool,
     span: Span,
 ) -> Result<ItemFn> {
-    let ForeignItemFn { sig, .. } = lib_function;
+    let ForeignItemFn {
+        sig, attrs, vis, ..
+    } = lib_function;
+
+    // async functions call the shim exported by `#[hot_function]` that returns
+    // a boxed future
+    let send = match hot_function_args(&attrs)?.send {
+        Some(send) if sig.asyncness.is_none() => {
+            return Err(syn::Error::new(
+                send.span(),
+                "`send` only applies to async functions",
+            ));
+        }
+        send => send.is_some(),
+    };
+    let async_wrapper_sig = sig
+        .asyncness
+        .is_some()
+        .then(|| async_wrapper_signature(&sig, send))
+        .transpose()?;
+    let sig = match async_wrapper_sig {
+        Some(_) => async_shim_signature(&sig, send)?,
+        None => sig,
+    };
+
+    let WrapperSignature {
+        mut sig,
+        arg_names,
+        fn_ptr,
+    } = wrapper_signature(sig)?;
 
     // the symbol inside the library we call needs to be a byte string
     // ending with a nul byte.
@@ -131,53 +569,624 @@ pub(crate) fn gen_hot_module_function_for(
         symbol_name.push(b'\0');
         LitByteStr::new(&symbol_name, Span::call_site())
     };
+    let function_name = LitStr::new(&fun_ident.to_string(), Span::call_site());
+    let catch_symbol_name = {
+        let mut symbol_name = catch_symbol_name(fun_ident).to_string().into_bytes();
+        symbol_name.push(b'\0');
+        LitByteStr::new(&symbol_name, Span::call_site())
+    };
 
-    let ret_type = &sig.output;
+    let err_msg_load_symbol = LitStr::new(
+        &format!("Cannot load library function {}", sig.ident),
+        Span::call_site(),
+    );
 
-    let mut input_types = Vec::new();
-    let mut input_names = Vec::new();
+    let mut call = unsafe_if(&sig.unsafety, quote::quote! { sym( #( #arg_names ),* ) });
+    if let Some(async_wrapper_sig) = async_wrapper_sig {
+        // the library version that created the future stays loaded until the
+        // future is dropped
+        sig.ident = async_wrapper_sig.ident;
+        sig.output = async_wrapper_sig.output;
+        call = quote::quote! { lib_loader.keep_loaded(#call) };
+    }
 
-    for arg in &sig.inputs {
-        match arg {
-            FnArg::Receiver(_) => {
-                eprintln!("[warn] exported library name has receiver / self type");
-                continue;
+    // `#[hot_function]` exports a shim that catches panics inside of the
+    // library, the executable can only catch them if both share `libstd`
+    let catch_shim = catch_panics.then(|| {
+        let mut catch_fn_ptr = fn_ptr.clone();
+        catch_fn_ptr.abi = None;
+        catch_fn_ptr.output = catch_output(&fn_ptr.output);
+        quote::quote! {
+            if let Ok(sym) = unsafe { lib_loader.get_symbol::<#catch_fn_ptr>(#catch_symbol_name) } {
+                return match #call {
+                    Ok(result) => result,
+                    Err(message) => __lib_resume_panic(message),
+                };
             }
-            FnArg::Typed(typed) => {
-                input_types.push(typed.ty.clone());
-                input_names.push(ident_from_pat(&typed.pat, &sig.ident, span)?);
+        }
+    });
+
+    // `__lib_call` holds the read lock and deals with panics
+    let block = syn::parse_quote_spanned! {span=>
+        {
+            __lib_call(#function_name, move |lib_loader| {
+                #catch_shim
+                let sym = unsafe {
+                    lib_loader
+                        .get_symbol::<#fn_ptr>(#symbol_name)
+                        .expect(#err_msg_load_symbol)
+                };
+                #call
+            })
+        }
+    };
+
+    // The wrapping function we export in the hot module that invokes the lib
+    // function using message sending
+    let function = ItemFn {
+        attrs: forwarded_attrs(&attrs).cloned().collect(),
+        vis,
+        sig,
+        block,
+    };
+
+    Ok(function)
+}
+
+// -=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-
+
+/// For the wrapper `function` of a library function generates
+///
+/// ```ignore
+/// pub fn try_do_stuff(arg: &str) -> Result<u32, hot_lib_reloader::HotPanic> {
+///     __lib_try("do_stuff", move || do_stuff(arg))
+/// }
+/// ```
+///
+/// that returns a panic of the library function as an error, see the
+/// `on_panic` option of the `hot_module` attribute. `callee` is the wrapper
+/// or, in the static fallback, the library function itself.
+fn gen_hot_try_function_for(
+    function: &ItemFn,
+    callee: &Path,
+    crate_name: &Path,
+    span: Span,
+) -> Result<ItemFn> {
+    let ident = &function.sig.ident;
+    let function_name = LitStr::new(&ident.to_string(), Span::call_site());
+    let doc = LitStr::new(
+        &format!(" Like [`{ident}`] but returns a panic of the library function as an error."),
+        Span::call_site(),
+    );
+
+    let WrapperSignature {
+        mut sig, arg_names, ..
+    } = wrapper_signature(function.sig.clone())?;
+    let call = unsafe_if(
+        &function.sig.unsafety,
+        quote::quote! { #callee( #( #arg_names ),* ) },
+    );
+
+    sig.ident = syn::Ident::new(&format!("try_{ident}"), ident.span());
+    let output = match &function.sig.output {
+        syn::ReturnType::Default => syn::parse_quote! { () },
+        syn::ReturnType::Type(_, ty) => (**ty).clone(),
+    };
+    sig.output = syn::parse_quote! {
+        -> ::std::result::Result<#output, #crate_name::HotPanic>
+    };
+
+    Ok(ItemFn {
+        attrs: cfg_attrs(&function.attrs)
+            .cloned()
+            .chain([
+                syn::parse_quote! { #[doc = #doc] },
+                syn::parse_quote! { #[allow(dead_code)] },
+            ])
+            .collect(),
+        vis: function.vis.clone(),
+        sig,
+        block: syn::parse_quote_spanned! {span=>
+            {
+                __lib_try(#function_name, move || #call)
+            }
+        },
+    })
+}
+
+/// Generates the wrapper of the library function `f` and, if the `on_panic`
+/// policy asks for it, its `try_` wrapper.
+pub(crate) fn gen_hot_function_with_try(
+    f: ForeignItemFn,
+    args: &HotModuleAttribute,
+    span: Span,
+) -> Result<(ItemFn, Option<ItemFn>)> {
+    // the future of an async function is polled outside of the wrapper
+    let on_panic = args.on_panic.filter(|_| f.sig.asyncness.is_none());
+    let function = gen_hot_module_function_for(f, on_panic.is_some(), span)?;
+    let try_function = match on_panic {
+        Some(on_panic) if on_panic.try_wrappers() => {
+            let callee = Path::from(function.sig.ident.clone());
+            Some(gen_hot_try_function_for(
+                &function,
+                &callee,
+                &args.crate_name,
+                span,
+            )?)
+        }
+        _ => None,
+    };
+    Ok((function, try_function))
+}
+
+/// For the library function `f` generates a statement adding the symbol its
+/// wrapper looks up to `probe_symbols`, see the `probe` option of the
+/// `hot_module` attribute.
+pub(crate) fn gen_probe_symbol(f: &ForeignItemFn, span: Span) -> Result<proc_macro2::TokenStream> {
+    let ident = match f.sig.asyncness {
+        Some(_) => async_symbol_name(&f.sig.ident),
+        None => f.sig.ident.clone(),
+    };
+    let symbol = LitStr::new(&ident.to_string(), Span::call_site());
+    let cfg = cfg_attrs(&f.attrs);
+    Ok(quote::quote_spanned! {span=>
+        #( #cfg )*
+        probe_symbols.push(#symbol);
+    })
+}
+
+// -=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-
+
+/// For a generic function declared with
+/// `#[hot_function(instantiate = [u32, f32])]` generates
+///
+/// ```ignore
+/// #[doc(hidden)]
+/// pub mod __hot_generic_double {
+///     use super::*;
+///     pub(super) mod sealed {
+///         pub trait Sealed {}
+///     }
+///     pub trait Instantiated: sealed::Sealed + Sized {
+///         fn __hot_call(__hot_arg0: Self) -> Self;
+///     }
+/// }
+///
+/// impl __hot_generic_double::sealed::Sealed for u32 {}
+/// impl __hot_generic_double::Instantiated for u32 {
+///     fn __hot_call(__hot_arg0: u32) -> u32 { __hot_generic__double__u32(__hot_arg0) }
+/// }
+/// /* same for f32 */
+///
+/// pub fn double<T: Add<Output = T> + Copy>(__hot_arg0: T) -> T
+/// where
+///     T: __hot_generic_double::Instantiated,
+/// {
+///     <T as __hot_generic_double::Instantiated>::__hot_call(__hot_arg0)
+/// }
+/// ```
+///
+/// where `__hot_generic__double__u32` is a normal hot function wrapper for the
+/// symbol exported by the library. Calling `double` with a type that is not
+/// listed is a compile error. `Instantiated` is sealed, code outside of the
+/// hot module cannot implement it for other types.
+pub(crate) fn gen_hot_generic_function_for(
+    generic: GenericFunction,
+    vis: Visibility,
+    span: Span,
+) -> Result<Vec<Item>> {
+    let ident = &generic.sig.ident;
+    let type_param = &generic.type_param;
+    let trait_mod = syn::Ident::new(&format!("__hot_generic_{ident}"), ident.span());
+    let call_ident = syn::Ident::new("__hot_call", Span::call_site());
+    let arg_names = generic.arg_names();
+    let unimplemented_msg = LitStr::new(
+        &format!("`{{Self}}` is not in the instantiation list of hot function `{ident}`"),
+        Span::call_site(),
+    );
+
+    let mut trait_sig = generic.instantiated_sig(&syn::parse_quote! { Self });
+    trait_sig.ident = call_ident.clone();
+
+    let mut items = vec![Item::Verbatim(quote::quote_spanned! {span=>
+        #[doc(hidden)]
+        #[allow(non_snake_case, dead_code)]
+        pub mod #trait_mod {
+            #[allow(unused_imports)]
+            use super::*;
+
+            pub(super) mod sealed {
+                pub trait Sealed {}
+            }
+
+            #[diagnostic::on_unimplemented(message = #unimplemented_msg)]
+            pub trait Instantiated: sealed::Sealed + Sized {
+                #trait_sig;
             }
         }
+    })];
+
+    for ty in &generic.instantiations {
+        let sig = generic.instantiated_sig(ty);
+        let symbol = sig.ident.clone();
+        let mut impl_sig = sig.clone();
+        impl_sig.ident = call_ident.clone();
+
+        let mut function = gen_hot_module_function_for(
+            ForeignItemFn {
+                attrs: Vec::new(),
+                vis: Visibility::Inherited,
+                sig,
+                semi_token: token::Semi::default(),
+            },
+            false,
+            span,
+        )?;
+        function.vis = Visibility::Inherited;
+        function
+            .attrs
+            .push(syn::parse_quote! { #[allow(non_snake_case)] });
+        items.push(Item::Fn(function));
+
+        let call = unsafe_if(
+            &generic.sig.unsafety,
+            quote::quote! { #symbol( #( #arg_names ),* ) },
+        );
+        items.push(Item::Verbatim(quote::quote_spanned! {span=>
+            impl #trait_mod::sealed::Sealed for #ty {}
+
+            impl #trait_mod::Instantiated for #ty {
+                #impl_sig {
+                    #call
+                }
+            }
+        }));
+    }
+
+    let mut sig = generic.sig.clone();
+    for (arg, name) in sig.inputs.iter_mut().zip(&arg_names) {
+        if let FnArg::Typed(typed) = arg {
+            typed.pat = syn::parse_quote! { #name };
+        }
     }
+    sig.generics
+        .make_where_clause()
+        .predicates
+        .push(syn::parse_quote! { #type_param: #trait_mod::Instantiated });
 
+    let call = unsafe_if(
+        &generic.sig.unsafety,
+        quote::quote! { <#type_param as #trait_mod::Instantiated>::#call_ident( #( #arg_names ),* ) },
+    );
+    items.push(Item::Fn(ItemFn {
+        // like the other wrappers, unused ones should not warn
+        attrs: std::iter::once(syn::parse_quote! { #[allow(dead_code)] })
+            .chain(
+                generic
+                    .attrs
+                    .iter()
+                    .filter(|attr| !attr.path().is_ident("cfg"))
+                    .cloned(),
+            )
+            .collect(),
+        vis,
+        sig,
+        block: syn::parse_quote_spanned! {span=>
+            {
+                #call
+            }
+        },
+    }));
+
+    let cfg_attrs = cfg_attrs(&generic.attrs).collect::<Vec<_>>();
+    if cfg_attrs.is_empty() {
+        return Ok(items);
+    }
+    Ok(items
+        .into_iter()
+        .map(|item| Item::Verbatim(quote::quote! { #( #cfg_attrs )* #item }))
+        .collect())
+}
+
+// -=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-
+
+/// For an impl block declared with `#[hot_methods]` generates a wrapper
+/// function per public method that calls the trampoline exported by the
+/// library. Also returns the statements that install those wrappers as hooks
+/// of the methods of the statically linked type.
+pub(crate) fn gen_hot_methods_for(
+    item_impl: ItemImpl,
+    span: Span,
+) -> Result<(Vec<ItemFn>, Vec<proc_macro2::TokenStream>)> {
+    if item_impl.trait_.is_some() || !item_impl.generics.params.is_empty() {
+        return Err(syn::Error::new(
+            span,
+            "#[hot_methods] can only be used on non-generic inherent impl blocks",
+        ));
+    }
+
+    let self_ty = &*item_impl.self_ty;
+    let impl_cfg = cfg_attrs(&item_impl.attrs).collect::<Vec<_>>();
+    let mut functions = Vec::new();
+    let mut hooks = Vec::new();
+
+    for item in &item_impl.items {
+        let method = match item {
+            ImplItem::Fn(method) if matches!(method.vis, Visibility::Public(_)) => method,
+            _ => continue,
+        };
+        let ident = &method.sig.ident;
+        let symbol = method_symbol_name(self_ty, ident)?;
+        let MethodSignature { free_fn_sig, .. } =
+            method_signature(self_ty, &method.sig, symbol.clone())?;
+        let cfg_attrs = impl_cfg
+            .iter()
+            .copied()
+            .chain(cfg_attrs(&method.attrs))
+            .collect::<Vec<_>>();
+
+        let mut function = gen_hot_module_function_for(
+            ForeignItemFn {
+                attrs: cfg_attrs.iter().copied().cloned().collect(),
+                vis: Visibility::Inherited,
+                sig: free_fn_sig,
+                semi_token: token::Semi::default(),
+            },
+            false,
+            span,
+        )?;
+        function.vis = Visibility::Inherited;
+        function
+            .attrs
+            .push(syn::parse_quote! { #[allow(non_snake_case)] });
+        functions.push(function);
+
+        let set_ident = syn::Ident::new(&format!("__hot_set_{ident}"), ident.span());
+        hooks.push(quote::quote_spanned! {span=>
+            #( #cfg_attrs )*
+            <#self_ty>::#set_ident(#symbol);
+        });
+    }
+
+    Ok((functions, hooks))
+}
+
+// -=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-
+
+/// For
+/// ```ignore
+/// #[hot_static]
+/// pub static SPEED: f32;
+/// ```
+/// generates
+/// ```ignore
+/// pub fn SPEED() -> hot_lib_reloader::HotStatic<f32> { /* ... */ }
+/// ```
+/// The returned guard keeps the library version the static belongs to loaded.
+pub(crate) fn gen_hot_static_accessor(
+    item_static: &syn::ForeignItemStatic,
+    crate_name: &Path,
+    span: Span,
+) -> proc_macro2::TokenStream {
+    let syn::ForeignItemStatic {
+        attrs,
+        vis,
+        ident,
+        ty,
+        ..
+    } = item_static;
+    let attrs = forwarded_attrs(attrs);
+
+    let symbol_name = {
+        let mut symbol_name = ident.to_string().into_bytes();
+        symbol_name.push(b'\0');
+        LitByteStr::new(&symbol_name, Span::call_site())
+    };
     let err_msg_load_symbol = LitStr::new(
-        &format!("Cannot load library function {}", sig.ident),
+        &format!("Cannot load library static {ident}"),
         Span::call_site(),
     );
 
-    let block = syn::parse_quote! {
-        {
+    quote::quote_spanned! {span=>
+        #( #attrs )*
+        #[allow(non_snake_case)]
+        #vis fn #ident() -> #crate_name::HotStatic<#ty> {
             let lib_loader = __lib_loader();
             let lib_loader = lib_loader.read().expect("lib loader RwLock read failed");
-            let sym = unsafe {
+            unsafe {
                 lib_loader
-                    .get_symbol::<fn( #( #input_types ),* ) #ret_type >(#symbol_name)
+                    .get_static::<#ty>(#symbol_name)
                     .expect(#err_msg_load_symbol)
-            };
-            sym( #( #input_names ),* )
+            }
         }
-    };
+    }
+}
 
-    // The wrapping function we export in the hot module that invokes the lib
-    // function using message sending
-    let function = ItemFn {
-        attrs: Vec::new(),
-        vis: Visibility::Public(token::Pub(Span::call_site())),
+// -=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-
+
+/// A constructor declared with
+/// ```ignore
+/// #[hot_trait(migrate = Behaviour::state)]
+/// pub fn new_behaviour(state: BehaviourState) -> hot_lib_reloader::HotDyn<dyn Behaviour> {}
+/// ```
+/// for the library function
+/// `fn new_behaviour(state: BehaviourState) -> Box<dyn Behaviour>`.
+#[derive(Clone)]
+pub(crate) struct HotTraitConstructor {
+    pub(crate) attrs: Vec<syn::Attribute>,
+    pub(crate) vis: Visibility,
+    /// Signature of the generated function, the argument is bound to `state`.
+    pub(crate) sig: syn::Signature,
+    pub(crate) state: syn::Ident,
+    pub(crate) state_ty: syn::Type,
+    /// The trait object type, e.g. `dyn Behaviour`.
+    pub(crate) object: syn::Type,
+    /// The trait method that extracts the state of an object.
+    pub(crate) migrate: Path,
+}
+
+impl HotTraitConstructor {
+    pub(crate) fn new(func: syn::ItemFn) -> Result<Self> {
+        let span = func.sig.ident.span();
+        let err = |msg: &str| {
+            Err(syn::Error::new(
+                span,
+                format!("#[hot_trait] function {}: {msg}", func.sig.ident),
+            ))
+        };
+
+        let attr = func
+            .attrs
+            .iter()
+            .find(|attr| attr.path().is_ident("hot_trait"))
+            .expect("called for #[hot_trait] functions");
+        let migrate = match &attr.meta {
+            syn::Meta::List(_) => attr.parse_args_with(|input: syn::parse::ParseStream| {
+                let ident = input.parse::<syn::Ident>()?;
+                if ident != "migrate" {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "expected `migrate = Trait::method`",
+                    ));
+                }
+                input.parse::<syn::Token![=]>()?;
+                input.parse::<Path>()
+            })?,
+            _ => return err("expected #[hot_trait(migrate = Trait::method)]"),
+        };
+
+        if !func.sig.generics.params.is_empty()
+            || func.sig.unsafety.is_some()
+            || func.sig.asyncness.is_some()
+            || func.sig.abi.is_some()
+        {
+            return err("the constructor has to be a plain, non-generic function");
+        }
+
+        let object = match &func.sig.output {
+            syn::ReturnType::Type(_, ty) => match &**ty {
+                syn::Type::Path(path) => {
+                    path.path
+                        .segments
+                        .last()
+                        .and_then(|segment| match &segment.arguments {
+                            syn::PathArguments::AngleBracketed(args) if args.args.len() == 1 => {
+                                match &args.args[0] {
+                                    syn::GenericArgument::Type(object) => Some(object.clone()),
+                                    _ => None,
+                                }
+                            }
+                            _ => None,
+                        })
+                }
+                _ => None,
+            },
+            syn::ReturnType::Default => None,
+        };
+        let Some(object) = object else {
+            return err("expected the return type `hot_lib_reloader::HotDyn<dyn Trait>`");
+        };
+
+        if func.sig.inputs.len() != 1 {
+            return err("expected the state of the object as the only argument");
+        }
+        let WrapperSignature { sig, arg_names, .. } = wrapper_signature(func.sig)?;
+        let FnArg::Typed(typed) = &sig.inputs[0] else {
+            unreachable!("wrapper_signature rejects self arguments");
+        };
+        let state_ty = (*typed.ty).clone();
+
+        Ok(Self {
+            attrs: forwarded_attrs(&func.attrs).cloned().collect(),
+            vis: func.vis,
+            state: arg_names[0].clone(),
+            sig,
+            state_ty,
+            object,
+            migrate,
+        })
+    }
+}
+
+/// For a [`HotTraitConstructor`] generates
+/// ```ignore
+/// pub fn new_behaviour(state: BehaviourState) -> hot_lib_reloader::HotDyn<dyn Behaviour> {
+///     fn __hot_trait_new(state: BehaviourState) -> hot_lib_reloader::KeepLoaded<Box<dyn Behaviour>> {
+///         /* calls the library constructor, the object keeps its library version loaded */
+///     }
+///     let updates = __lib_update_cursor();
+///     hot_lib_reloader::HotDyn::new(updates, __hot_trait_new(state), |previous| {
+///         __hot_trait_new(Behaviour::state(previous))
+///     })
+/// }
+/// ```
+pub(crate) fn gen_hot_trait_constructor(
+    ctor: &HotTraitConstructor,
+    crate_name: &Path,
+    span: Span,
+) -> proc_macro2::TokenStream {
+    let HotTraitConstructor {
+        attrs,
+        vis,
         sig,
-        block,
+        state,
+        state_ty,
+        object,
+        migrate,
+    } = ctor;
+
+    let symbol_name = {
+        let mut symbol_name = sig.ident.to_string().into_bytes();
+        symbol_name.push(b'\0');
+        LitByteStr::new(&symbol_name, Span::call_site())
     };
+    let err_msg_load_symbol = LitStr::new(
+        &format!("Cannot load library function {}", sig.ident),
+        Span::call_site(),
+    );
 
-    Ok(function)
+    quote::quote_spanned! {span=>
+        #( #attrs )*
+        #vis #sig {
+            fn __hot_trait_new(#state: #state_ty) -> #crate_name::KeepLoaded<::std::boxed::Box<#object>> {
+                let lib_loader = __lib_loader();
+                let lib_loader = lib_loader.read().expect("lib loader RwLock read failed");
+                let sym = unsafe {
+                    lib_loader
+                        .get_symbol::<fn(#state_ty) -> ::std::boxed::Box<#object>>(#symbol_name)
+                        .expect(#err_msg_load_symbol)
+                };
+                // the vtable of the object lives in this library version
+                lib_loader.keep_loaded(sym(#state))
+            }
+
+            // created before the object so that a reload in between is not missed
+            let updates = __lib_update_cursor();
+            #crate_name::HotDyn::new(updates, __hot_trait_new(#state), |previous| {
+                __hot_trait_new(#migrate(previous))
+            })
+        }
+    }
+}
+
+// -=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-
+
+/// Includes the library source files read by `hot_functions_from_file!` and
+/// `hot_functions_from_crate!` so that cargo rebuilds the executable and the
+/// wrappers are regenerated when those files change.
+pub(crate) fn gen_source_dependencies(
+    files: &[std::path::PathBuf],
+    span: Span,
+) -> proc_macro2::TokenStream {
+    // the paths are absolute, `include_bytes!` would resolve relative paths
+    // against the file of the macro call
+    let files = files
+        .iter()
+        .map(|file| LitStr::new(&file.to_string_lossy(), Span::call_site()));
+
+    quote::quote_spanned! {span=>
+        #( const _: &[u8] = include_bytes!(#files); )*
+    }
 }
 
 // -=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-
@@ -185,14 +1194,69 @@ pub(crate) fn gen_hot_module_function_for(
 /// For something like
 /// ```ignore
 /// #[lib_change_subscription]
-/// pub fn rx() -> std::sync::mpsc::Receiver<hot_lib_reloader::ChangedEvent> {
+/// pub fn subscribe() -> hot_lib_reloader::LibReloadObserver {
 ///     __lib_loader_subscription()
 /// }
+///
+/// #[lib_change_subscription]
+/// pub fn subscribe_with(options: hot_lib_reloader::SubscriptionOptions) -> hot_lib_reloader::LibReloadObserver {
+///     __lib_loader_subscription_with(options)
+/// }
 /// ```
 pub(crate) fn gen_lib_change_subscription_function(
     f_decl: ForeignItemFn,
     span: Span,
 ) -> Result<ItemFn> {
+    let ForeignItemFn {
+        mut sig,
+        vis,
+        attrs,
+        ..
+    } = f_decl;
+
+    let block = match sig.inputs.len() {
+        0 => syn::parse_quote_spanned! {span=>
+            {
+                __lib_loader_subscription()
+            }
+        },
+        1 => {
+            let options = match &mut sig.inputs[0] {
+                FnArg::Typed(typed) => {
+                    let options = arg_name(0, &typed.pat);
+                    typed.pat = Box::new(syn::parse_quote! { #options });
+                    options
+                }
+                FnArg::Receiver(_) => {
+                    return Err(syn::Error::new(
+                        span,
+                        "lib_change_subscription function cannot take self",
+                    ));
+                }
+            };
+            syn::parse_quote_spanned! {span=>
+                {
+                    __lib_loader_subscription_with(#options)
+                }
+            }
+        }
+        _ => {
+            return Err(syn::Error::new(
+                span,
+                "lib_change_subscription function expects no arguments or a single SubscriptionOptions argument",
+            ));
+        }
+    };
+
+    Ok(ItemFn {
+        attrs,
+        vis,
+        sig,
+        block,
+    })
+}
+
+pub(crate) fn gen_lib_version_function(f_decl: ForeignItemFn, span: Span) -> Result<ItemFn> {
     let ForeignItemFn {
         sig, vis, attrs, ..
     } = f_decl;
@@ -203,13 +1267,13 @@ pub(crate) fn gen_lib_change_subscription_function(
         sig,
         block: syn::parse_quote_spanned! {span=>
             {
-                __lib_loader_subscription()
+                __lib_notifier().version()
             }
         },
     })
 }
 
-pub(crate) fn gen_lib_version_function(f_decl: ForeignItemFn, span: Span) -> Result<ItemFn> {
+pub(crate) fn gen_lib_was_updated_function(f_decl: ForeignItemFn, span: Span) -> Result<ItemFn> {
     let ForeignItemFn {
         sig, vis, attrs, ..
     } = f_decl;
@@ -220,13 +1284,13 @@ pub(crate) fn gen_lib_version_function(f_decl: ForeignItemFn, span: Span) -> Res
         sig,
         block: syn::parse_quote_spanned! {span=>
             {
-                VERSION.load(::std::sync::atomic::Ordering::Acquire)
+                WAS_UPDATED.swap(false,::std::sync::atomic::Ordering::AcqRel)
             }
         },
     })
 }
 
-pub(crate) fn gen_lib_was_updated_function(f_decl: ForeignItemFn, span: Span) -> Result<ItemFn> {
+pub(crate) fn gen_lib_init_function(f_decl: ForeignItemFn, span: Span) -> Result<ItemFn> {
     let ForeignItemFn {
         sig, vis, attrs, ..
     } = f_decl;
@@ -237,8 +1301,330 @@ pub(crate) fn gen_lib_was_updated_function(f_decl: ForeignItemFn, span: Span) ->
         sig,
         block: syn::parse_quote_spanned! {span=>
             {
-                WAS_UPDATED.swap(false,::std::sync::atomic::Ordering::AcqRel)
+                __lib_loader_init().map(|_| ())
+            }
+        },
+    })
+}
+
+pub(crate) fn gen_lib_info_function(f_decl: ForeignItemFn, span: Span) -> Result<ItemFn> {
+    let ForeignItemFn {
+        sig, vis, attrs, ..
+    } = f_decl;
+
+    Ok(ItemFn {
+        attrs,
+        vis,
+        sig,
+        block: syn::parse_quote_spanned! {span=>
+            {
+                __lib_info()
             }
         },
     })
 }
+
+pub(crate) fn gen_lib_generation_guard_function(
+    f_decl: ForeignItemFn,
+    span: Span,
+) -> Result<ItemFn> {
+    let ForeignItemFn {
+        sig, vis, attrs, ..
+    } = f_decl;
+
+    Ok(ItemFn {
+        attrs,
+        vis,
+        sig,
+        block: syn::parse_quote_spanned! {span=>
+            {
+                __lib_generation_guard()
+            }
+        },
+    })
+}
+
+pub(crate) fn gen_lib_update_cursor_function(f_decl: ForeignItemFn, span: Span) -> Result<ItemFn> {
+    let ForeignItemFn {
+        sig, vis, attrs, ..
+    } = f_decl;
+
+    Ok(ItemFn {
+        attrs,
+        vis,
+        sig,
+        block: syn::parse_quote_spanned! {span=>
+            {
+                __lib_update_cursor()
+            }
+        },
+    })
+}
+
+// -=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-=-
+
+/// The items generated for the exports of a library module.
+#[derive(Default)]
+pub(crate) struct ExportItems {
+    pub(crate) items: Vec<Item>,
+    /// the `try_` wrappers, not part of `items`
+    pub(crate) try_functions: Vec<ItemFn>,
+    /// the `try_` wrappers of the static fallback, they call the functions of
+    /// `fallback_module` directly
+    pub(crate) fallback_try_functions: Vec<ItemFn>,
+    /// modules of the static fallback mirroring the library modules, only
+    /// generated by `gen_hot_crate_items`
+    pub(crate) fallback_modules: Vec<Item>,
+    /// statements installing the hooks of `#[hot_methods]` impl blocks
+    pub(crate) method_hooks: Vec<proc_macro2::TokenStream>,
+    /// statements adding the symbols of the hot functions to `probe_symbols`
+    pub(crate) probe_symbols: Vec<proc_macro2::TokenStream>,
+}
+
+/// Generates the wrappers for the items read by `hot_functions_from_file!`.
+/// If `fallback_module` is the path of the library module in the statically
+/// linked library, `try_` wrappers calling into it are generated for the
+/// static fallback, too.
+pub(crate) fn gen_items_for_exports(
+    exports: FileExports,
+    args: &HotModuleAttribute,
+    fallback_module: Option<&Path>,
+) -> Result<ExportItems> {
+    let FileExports {
+        functions,
+        method_impls,
+        generic_functions,
+    } = exports;
+
+    let mut items = Vec::new();
+    let mut try_functions = Vec::new();
+    let mut fallback_try_functions = Vec::new();
+    let mut method_hooks = Vec::new();
+    let mut probe_symbols = Vec::new();
+
+    for (f, span) in functions {
+        probe_symbols.push(gen_probe_symbol(&f, span)?);
+        let (function, try_function) = gen_hot_function_with_try(f, args, span)?;
+        if let (Some(_), Some(fallback_module)) = (&try_function, fallback_module) {
+            let ident = &function.sig.ident;
+            let callee = syn::parse_quote! { #fallback_module::#ident };
+            fallback_try_functions.push(gen_hot_try_function_for(
+                &function,
+                &callee,
+                &args.crate_name,
+                span,
+            )?);
+        }
+        items.push(Item::Fn(function));
+        try_functions.extend(try_function);
+    }
+    for (generic, span) in generic_functions {
+        let vis = Visibility::Public(token::Pub(span));
+        items.extend(gen_hot_generic_function_for(generic, vis, span)?);
+    }
+    for (item_impl, span) in method_impls {
+        let (functions, hooks) = gen_hot_methods_for(item_impl, span)?;
+        items.extend(functions.into_iter().map(Item::Fn));
+        method_hooks.extend(hooks);
+    }
+
+    Ok(ExportItems {
+        items,
+        try_functions,
+        fallback_try_functions,
+        method_hooks,
+        probe_symbols,
+        ..Default::default()
+    })
+}
+
+/// Generates the items for `hot_functions_from_crate!`. The wrappers of the
+/// library root go directly into the hot module, for each submodule of the
+/// library a module of the same name is generated:
+///
+/// ```ignore
+/// pub mod systems {
+///     #[allow(unused_imports)]
+///     use super::*;
+///     // types etc. of the library module, only if it is reachable
+///     #[allow(unused_imports)]
+///     pub use ::lib::systems::*;
+///     // private imports of the library module, made absolute
+///     #[allow(unused_imports)]
+///     use ::lib::state::State;
+///     // re-exports of the library module now point to the wrappers
+///     pub use self::movement::update;
+///     // other re-exports still point to the library module
+///     pub use ::lib::systems::Velocity;
+///
+///     pub fn render(state: &State) { /* calls into the library */ }
+///
+///     pub mod movement { /* ... */ }
+///
+///     pub(super) fn __hot_method_hooks() { /* ... */ }
+/// }
+/// ```
+///
+/// With a static fallback, the `try_` wrappers of public library modules go
+/// into modules of the same structure that call the statically linked
+/// functions, the rest of the library module is re-exported.
+pub(crate) fn gen_hot_crate_items(
+    lib: &syn::Ident,
+    module: CrateModule,
+    args: &HotModuleAttribute,
+    span: Span,
+) -> Result<ExportItems> {
+    let mut uses = Vec::new();
+    for item_use in &module.uses {
+        let (paths, vis) = match item_use.vis {
+            Visibility::Public(_) => (UsePaths::Mirror, item_use.vis.clone()),
+            // only re-exports are followed at the root, the hot module is in
+            // charge of its own imports
+            _ if module.path.is_empty() => continue,
+            _ => (UsePaths::Library(lib), Visibility::Inherited),
+        };
+        let leading_colon = match paths {
+            UsePaths::Library(_) => Some(Default::default()),
+            UsePaths::Mirror => None,
+        };
+        if let Some(tree) = module.rewrite_use_tree(&item_use.tree, paths) {
+            uses.push(syn::ItemUse {
+                attrs: vec![syn::parse_quote! { #[allow(unused_imports)] }],
+                vis,
+                leading_colon,
+                tree,
+                ..item_use.clone()
+            });
+        }
+    }
+    // e.g. types re-exported from a private module, see `split_reexports`
+    let module_path = &module.path;
+    for item_use in &module.library_reexports {
+        let syn::ItemUse { vis, tree, .. } = item_use;
+        uses.push(syn::parse_quote_spanned! {span=>
+            #[allow(unused_imports)]
+            #vis use ::#lib #( ::#module_path )*::#tree;
+        });
+    }
+
+    let CrateModule {
+        ident,
+        vis,
+        cfg,
+        path,
+        is_public,
+        exports,
+        children,
+        ..
+    } = module;
+
+    // the crate root of the fallback re-exports the library root
+    let fallback_module: Option<Path> =
+        (args.static_fallback.is_some() && is_public && ident.is_some())
+            .then(|| syn::parse_quote_spanned! {span=> ::#lib #( ::#path )* });
+
+    let ExportItems {
+        mut items,
+        try_functions,
+        fallback_try_functions,
+        mut method_hooks,
+        mut probe_symbols,
+        ..
+    } = gen_items_for_exports(exports, args, fallback_module.as_ref())?;
+    // re-exports are covered by the glob import of the library module
+    let fallback_uses = match &fallback_module {
+        Some(_) => uses
+            .iter()
+            .filter(|item_use| matches!(item_use.vis, Visibility::Inherited))
+            .cloned()
+            .collect(),
+        None => Vec::new(),
+    };
+    items.splice(0..0, uses.into_iter().map(Item::Use));
+
+    let mut fallback_modules = Vec::new();
+    for child in children {
+        let child_ident = child.ident.clone();
+        let child_cfg = child.cfg.clone();
+        let child_items = gen_hot_crate_items(lib, child, args, span)?;
+        items.extend(child_items.items);
+        fallback_modules.extend(child_items.fallback_modules);
+        method_hooks.push(quote::quote_spanned! {span=>
+            #child_cfg
+            #child_ident::__hot_method_hooks();
+        });
+        let child_probe_symbols = child_items.probe_symbols;
+        probe_symbols.push(quote::quote_spanned! {span=>
+            #child_cfg
+            {
+                #( #child_probe_symbols )*
+            }
+        });
+    }
+
+    let Some(ident) = ident else {
+        // crate root
+        return Ok(ExportItems {
+            items,
+            try_functions,
+            method_hooks,
+            probe_symbols,
+            fallback_modules,
+            ..Default::default()
+        });
+    };
+
+    let vis = match vis {
+        Visibility::Restricted(restricted) if restricted.in_token.is_some() => {
+            syn::parse_quote! { pub(crate) }
+        }
+        vis => vis,
+    };
+    let glob = is_public.then(|| {
+        quote::quote_spanned! {span=>
+            #[allow(unused_imports)]
+            pub use ::#lib #( ::#path )*::*;
+        }
+    });
+
+    let module = Item::Verbatim(quote::quote_spanned! {span=>
+        #cfg
+        #vis mod #ident {
+            #[allow(unused_imports)]
+            use super::*;
+            #glob
+
+            #( #items )*
+
+            #( #try_functions )*
+
+            pub(super) fn __hot_method_hooks() {
+                #( #method_hooks )*
+            }
+        }
+    });
+
+    let fallback_module = fallback_module.map(|fallback_module| {
+        Item::Verbatim(quote::quote_spanned! {span=>
+            #cfg
+            #vis mod #ident {
+                #[allow(unused_imports)]
+                use super::*;
+                #[allow(unused_imports)]
+                pub use #fallback_module::*;
+                #( #fallback_uses )*
+
+                #( #fallback_try_functions )*
+
+                #( #fallback_modules )*
+            }
+        })
+    });
+
+    Ok(ExportItems {
+        items: vec![module],
+        probe_symbols,
+        fallback_modules: fallback_module.into_iter().collect(),
+        ..Default::default()
+    })
+}
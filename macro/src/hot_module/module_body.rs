@@ -1,28 +1,55 @@
-use quote::ToTokens;
 use syn::LitBool;
+use syn::parse::Parser;
 use syn::{
     Attribute, ForeignItemFn, Ident, Item, ItemMacro, LitStr, Macro, Result, Visibility,
-    spanned::Spanned, token,
+    punctuated::Punctuated, spanned::Spanned, token,
 };
 
 use super::HotModuleAttribute;
 use super::code_gen::{
-    gen_hot_module_function_for, gen_lib_change_subscription_function, generate_lib_loader_items,
+    gen_hot_crate_items, gen_hot_generic_function_for, gen_hot_methods_for, gen_items_for_exports,
+    gen_lib_change_subscription_function, gen_probe_symbol, generate_lib_loader_items,
+};
+use crate::cfg::{LibConfig, cfg_attrs};
+use crate::crate_scan::read_crate;
+use crate::hot_module::code_gen::{
+    FallbackItem, HotTraitConstructor, gen_hot_function_with_try, gen_hot_static_accessor,
+    gen_hot_trait_constructor, gen_lib_generation_guard_function, gen_lib_info_function,
+    gen_lib_init_function, gen_lib_update_cursor_function, gen_lib_version_function,
+    gen_lib_was_updated_function, gen_source_dependencies, gen_static_fallback_items,
+};
+use crate::util::{
+    GenericFunction, foreign_fn_with_abi, forwarded_attrs, is_hot_function_attr,
+    is_hot_methods_attr, parse_hot_function_attr, read_functions_from_file,
 };
-use crate::hot_module::code_gen::{gen_lib_version_function, gen_lib_was_updated_function};
-use crate::util::read_functions_from_file;
 
 pub(crate) struct HotModule {
     pub(crate) vis: Visibility,
     pub(crate) ident: Ident,
     pub(crate) items: Vec<Item>,
+    /// statements installing the `#[hot_methods]` hooks
+    pub(crate) method_hooks: Vec<proc_macro2::TokenStream>,
+    /// statements adding the symbols of the hot functions to `probe_symbols`
+    pub(crate) probe_symbols: Vec<proc_macro2::TokenStream>,
+    /// `#[hot_static]` declarations, their accessors refer to the crate
+    /// argument of the `hot_module` attribute
+    pub(crate) hot_statics: Vec<syn::ForeignItemStatic>,
+    /// `#[hot_trait]` constructors, like `hot_statics`
+    pub(crate) hot_traits: Vec<HotTraitConstructor>,
+    /// library source files read while expanding, changes to them need to
+    /// trigger a rebuild
+    pub(crate) source_files: Vec<std::path::PathBuf>,
+    /// contents of the module when reloading is disabled
+    pub(crate) fallback_items: Vec<FallbackItem>,
     #[allow(dead_code)]
     pub(crate) attributes: Vec<Attribute>,
-    pub(crate) hot_module_args: Option<super::HotModuleAttribute>,
+    pub(crate) hot_module_args: HotModuleAttribute,
 }
 
-impl syn::parse::Parse for HotModule {
-    fn parse(stream: syn::parse::ParseStream) -> Result<Self> {
+impl HotModule {
+    /// Parses the module the `hot_module` attribute with the arguments `args`
+    /// is applied to.
+    pub(crate) fn parse(stream: syn::parse::ParseStream, args: HotModuleAttribute) -> Result<Self> {
         let attributes = syn::Attribute::parse_outer(stream)?;
 
         let vis = stream
@@ -37,6 +64,12 @@ impl syn::parse::Parse for HotModule {
         syn::braced!(module_body_stream in stream);
 
         let mut items = Vec::new();
+        let mut method_hooks = Vec::new();
+        let mut probe_symbols = Vec::new();
+        let mut hot_statics = Vec::new();
+        let mut hot_traits = Vec::new();
+        let mut source_files = Vec::new();
+        let mut fallback_items = Vec::new();
 
         while !module_body_stream.is_empty() {
             let item = module_body_stream.parse::<syn::Item>()?;
@@ -47,55 +80,59 @@ impl syn::parse::Parse for HotModule {
                     mac: Macro { path, tokens, .. },
                     ..
                 }) if path.is_ident("hot_functions_from_file") => {
-                    let span = path.span();
-                    let mut iter = tokens.into_iter();
-
-                    // get filename
-                    let file_name = iter
-                        .next()
-                        .ok_or_else(|| {
-                            syn::Error::new(span, "expected path to file as a literal string")
-                        })
-                        .and_then(|t| syn::parse2::<LitStr>(t.into_token_stream()))?;
-
-                    // parse optional `ignore_no_mangle = true`
-                    let ignore_no_mangle = if let Some(tokens) = iter.next() {
-                        match tokens {
-                            proc_macro2::TokenTree::Punct(p) if p.as_char() == ',' => {
-                                let ident = iter
-                                    .next()
-                                    .ok_or_else(|| syn::Error::new(ident.span(), "expected ident"))
-                                    .and_then(|t| syn::parse2::<Ident>(t.to_token_stream()))?;
-                                if ident != "ignore_no_mangle" {
-                                    return Err(syn::Error::new(ident.span(), "unexpected input"));
-                                }
-
-                                iter.next()
-                                    .ok_or_else(|| syn::Error::new(ident.span(), "expected ="))
-                                    .and_then(|t| syn::parse2::<token::Eq>(t.to_token_stream()))?;
-
-                                let val = iter
-                                    .next()
-                                    .ok_or_else(|| {
-                                        syn::Error::new(ident.span(), "expected true or false")
-                                    })
-                                    .and_then(|t| syn::parse2::<LitBool>(t.to_token_stream()))?;
-                                val.value()
-                            }
-                            other => {
-                                return Err(syn::Error::new(other.span(), "expected comma"));
-                            }
-                        }
-                    } else {
-                        false
-                    };
+                    let (file_name, config, module) = parse_source_macro_args(tokens, path.span())?;
+                    let (file, exports) = read_functions_from_file(file_name, &config)?;
+                    items.push(Item::Verbatim(config.warnings()));
+                    // the whole library module, `#[cfg]`s are then evaluated
+                    // by the compiler
+                    fallback_items.push(FallbackItem::Use {
+                        attrs: Vec::new(),
+                        vis: syn::parse_quote! { pub },
+                        path: module.unwrap_or_else(|| library_module_path(&file, path.span())),
+                        glob: true,
+                    });
+                    source_files.push(file);
+                    let file_items = gen_items_for_exports(exports, &args, None)?;
+                    items.extend(file_items.items);
+                    push_try_functions(file_items.try_functions, &mut items, &mut fallback_items);
+                    method_hooks.extend(file_items.method_hooks);
+                    probe_symbols.extend(file_items.probe_symbols);
+                }
 
-                    // read from file
-                    let functions = read_functions_from_file(file_name, ignore_no_mangle)?;
-                    for (f, span) in functions {
-                        let f = gen_hot_module_function_for(f, span)?;
-                        items.push(Item::Fn(f));
+                // parses the hot_functions_from_crate!("path/to/lib") marker
+                Item::Macro(ItemMacro {
+                    mac: Macro { path, tokens, .. },
+                    ..
+                }) if path.is_ident("hot_functions_from_crate") => {
+                    let span = path.span();
+                    let (crate_dir, config, module) = parse_source_macro_args(tokens, span)?;
+                    if module.is_some() {
+                        return Err(syn::Error::new(
+                            span,
+                            "`module` only applies to hot_functions_from_file!",
+                        ));
                     }
+                    let (lib, root, manifest) = read_crate(crate_dir, &config)?;
+                    items.push(Item::Verbatim(config.warnings()));
+                    source_files.push(manifest);
+                    source_files.extend(root.source_files());
+                    fallback_items.push(FallbackItem::Use {
+                        attrs: Vec::new(),
+                        vis: syn::parse_quote! { pub },
+                        path: Vec::new(),
+                        glob: true,
+                    });
+                    let crate_items = gen_hot_crate_items(&lib, root, &args, span)?;
+                    items.extend(crate_items.items);
+                    push_try_functions(crate_items.try_functions, &mut items, &mut fallback_items);
+                    fallback_items.extend(
+                        crate_items
+                            .fallback_modules
+                            .into_iter()
+                            .map(|module| FallbackItem::Item(Box::new(module))),
+                    );
+                    method_hooks.extend(crate_items.method_hooks);
+                    probe_symbols.extend(crate_items.probe_symbols);
                 }
 
                 // parses and code gens
@@ -115,6 +152,7 @@ impl syn::parse::Parse for HotModule {
                         semi_token: token::Semi::default(),
                     };
                     let f = gen_lib_change_subscription_function(f, span)?;
+                    fallback_items.push(FallbackItem::Item(Box::new(Item::Fn(f.clone()))));
                     items.push(Item::Fn(f));
                 }
 
@@ -135,6 +173,7 @@ impl syn::parse::Parse for HotModule {
                         semi_token: token::Semi::default(),
                     };
                     let f = gen_lib_version_function(f, span)?;
+                    fallback_items.push(FallbackItem::Item(Box::new(Item::Fn(f.clone()))));
                     items.push(Item::Fn(f));
                 }
 
@@ -155,12 +194,114 @@ impl syn::parse::Parse for HotModule {
                         semi_token: token::Semi::default(),
                     };
                     let f = gen_lib_was_updated_function(f, span)?;
+                    fallback_items.push(FallbackItem::Item(Box::new(Item::Fn(f.clone()))));
+                    items.push(Item::Fn(f));
+                }
+
+                // parses and code gens
+                // #[lib_update_cursor]
+                // pub fn update_cursor() -> hot_lib_reloader::UpdateCursor {}
+                syn::Item::Fn(func)
+                    if func
+                        .attrs
+                        .iter()
+                        .any(|attr| attr.path().is_ident("lib_update_cursor")) =>
+                {
+                    let span = func.span();
+                    let f = ForeignItemFn {
+                        attrs: Vec::new(),
+                        vis: func.vis,
+                        sig: func.sig,
+                        semi_token: token::Semi::default(),
+                    };
+                    let f = gen_lib_update_cursor_function(f, span)?;
+                    fallback_items.push(FallbackItem::Item(Box::new(Item::Fn(f.clone()))));
+                    items.push(Item::Fn(f));
+                }
+
+                // parses and code gens
+                // #[lib_init]
+                // pub fn init() -> Result<(), hot_lib_reloader::HotReloaderError> {}
+                syn::Item::Fn(func)
+                    if func
+                        .attrs
+                        .iter()
+                        .any(|attr| attr.path().is_ident("lib_init")) =>
+                {
+                    let span = func.span();
+                    let f = ForeignItemFn {
+                        attrs: Vec::new(),
+                        vis: func.vis,
+                        sig: func.sig,
+                        semi_token: token::Semi::default(),
+                    };
+                    let f = gen_lib_init_function(f, span)?;
+                    fallback_items.push(FallbackItem::Item(Box::new(Item::Fn(f.clone()))));
+                    items.push(Item::Fn(f));
+                }
+
+                // parses and code gens
+                // #[lib_info]
+                // pub fn info() -> hot_lib_reloader::LibInfo {}
+                syn::Item::Fn(func)
+                    if func
+                        .attrs
+                        .iter()
+                        .any(|attr| attr.path().is_ident("lib_info")) =>
+                {
+                    let span = func.span();
+                    let f = ForeignItemFn {
+                        attrs: Vec::new(),
+                        vis: func.vis,
+                        sig: func.sig,
+                        semi_token: token::Semi::default(),
+                    };
+                    let f = gen_lib_info_function(f, span)?;
+                    fallback_items.push(FallbackItem::Item(Box::new(Item::Fn(f.clone()))));
+                    items.push(Item::Fn(f));
+                }
+
+                // parses and code gens
+                // #[lib_generation_guard]
+                // pub fn generation_guard() -> Option<hot_lib_reloader::GenerationGuard> {}
+                syn::Item::Fn(func)
+                    if func
+                        .attrs
+                        .iter()
+                        .any(|attr| attr.path().is_ident("lib_generation_guard")) =>
+                {
+                    let span = func.span();
+                    let f = ForeignItemFn {
+                        attrs: Vec::new(),
+                        vis: func.vis,
+                        sig: func.sig,
+                        semi_token: token::Semi::default(),
+                    };
+                    let f = gen_lib_generation_guard_function(f, span)?;
+                    fallback_items.push(FallbackItem::Item(Box::new(Item::Fn(f.clone()))));
                     items.push(Item::Fn(f));
                 }
 
+                // parses
+                // #[hot_trait(migrate = Behaviour::state)]
+                // pub fn new_behaviour(state: BehaviourState) -> hot_lib_reloader::HotDyn<dyn Behaviour> {}
+                syn::Item::Fn(func)
+                    if func
+                        .attrs
+                        .iter()
+                        .any(|attr| attr.path().is_ident("hot_trait")) =>
+                {
+                    let ctor = HotTraitConstructor::new(func)?;
+                    fallback_items.push(FallbackItem::Trait(Box::new(ctor.clone())));
+                    hot_traits.push(ctor);
+                }
+
                 // parses and code gens
                 // #[hot_function]
                 // fn do_stuff(arg: &str) -> u32 {}
+                // or
+                // #[hot_function(instantiate = [u32, f32])]
+                // fn double<T: Add<Output = T> + Copy>(x: T) -> T {}
                 syn::Item::Fn(func)
                     if func
                         .attrs
@@ -168,14 +309,45 @@ impl syn::parse::Parse for HotModule {
                         .any(|attr| attr.path().is_ident("hot_function")) =>
                 {
                     let span = func.span();
+                    fallback_items.push(FallbackItem::Use {
+                        attrs: cfg_attrs(&func.attrs).cloned().collect(),
+                        vis: func.vis.clone(),
+                        path: vec![func.sig.ident.clone()],
+                        glob: false,
+                    });
+                    let attr = func.attrs.iter().find(|attr| is_hot_function_attr(attr));
+                    if let Some(instantiations) = attr
+                        .map(parse_hot_function_attr)
+                        .transpose()?
+                        .and_then(|args| args.instantiations)
+                    {
+                        let mut generic = GenericFunction::new(func.sig, instantiations)?;
+                        generic.attrs = forwarded_attrs(&func.attrs).cloned().collect();
+                        items.extend(gen_hot_generic_function_for(generic, func.vis, span)?);
+                        continue;
+                    }
                     let f = ForeignItemFn {
-                        attrs: Vec::new(),
+                        attrs: func.attrs,
                         vis: func.vis,
                         sig: func.sig,
                         semi_token: token::Semi::default(),
                     };
-                    let f = gen_hot_module_function_for(f, span)?;
+                    probe_symbols.push(gen_probe_symbol(&f, span)?);
+                    let (f, try_function) = gen_hot_function_with_try(f, &args, span)?;
                     items.push(Item::Fn(f));
+                    push_try_functions(try_function, &mut items, &mut fallback_items);
+                }
+
+                // parses and code gens
+                // #[hot_methods]
+                // impl Counter {
+                //     pub fn value(&self) -> i32 {}
+                // }
+                syn::Item::Impl(item_impl) if item_impl.attrs.iter().any(is_hot_methods_attr) => {
+                    let span = item_impl.span();
+                    let (functions, hooks) = gen_hot_methods_for(item_impl, span)?;
+                    items.extend(functions.into_iter().map(Item::Fn));
+                    method_hooks.extend(hooks);
                 }
 
                 // parses and code gens
@@ -190,13 +362,21 @@ impl syn::parse::Parse for HotModule {
                         .any(|attr| attr.path().is_ident("hot_functions")) =>
                 {
                     for item in foreign_mod.items {
-                        match item {
-                            syn::ForeignItem::Fn(f) => {
+                        match foreign_fn_with_abi(item, &foreign_mod.abi)? {
+                            Some(f) => {
                                 let span = f.span();
-                                let f = gen_hot_module_function_for(f, span)?;
+                                fallback_items.push(FallbackItem::Use {
+                                    attrs: cfg_attrs(&f.attrs).cloned().collect(),
+                                    vis: f.vis.clone(),
+                                    path: vec![f.sig.ident.clone()],
+                                    glob: false,
+                                });
+                                probe_symbols.push(gen_probe_symbol(&f, span)?);
+                                let (f, try_function) = gen_hot_function_with_try(f, &args, span)?;
                                 items.push(Item::Fn(f));
+                                push_try_functions(try_function, &mut items, &mut fallback_items);
                             }
-                            _ => {
+                            None => {
                                 eprintln!(
                                     "[warn] hot_functions extern block includes unexpected items"
                                 );
@@ -205,8 +385,32 @@ impl syn::parse::Parse for HotModule {
                     }
                 }
 
+                // parses
+                // #[hot_static]
+                // pub static SPEED: f32;
+                Item::Verbatim(tokens) => match parse_hot_static(&tokens) {
+                    Some(item_static) => {
+                        if !matches!(item_static.mutability, syn::StaticMutability::None) {
+                            return Err(syn::Error::new(
+                                item_static.ident.span(),
+                                "#[hot_static] does not support `static mut`",
+                            ));
+                        }
+                        fallback_items.push(FallbackItem::Static(item_static.clone()));
+                        hot_statics.push(item_static);
+                    }
+                    None => {
+                        fallback_items
+                            .push(FallbackItem::Item(Box::new(Item::Verbatim(tokens.clone()))));
+                        items.push(Item::Verbatim(tokens));
+                    }
+                },
+
                 // otherwise just use the item as is
-                item => items.push(item),
+                item => {
+                    fallback_items.push(FallbackItem::Item(Box::new(item.clone())));
+                    items.push(item);
+                }
             };
         }
 
@@ -214,51 +418,177 @@ impl syn::parse::Parse for HotModule {
             ident,
             vis,
             items,
+            method_hooks,
+            probe_symbols,
+            hot_statics,
+            hot_traits,
+            source_files,
+            fallback_items,
             attributes,
-            hot_module_args: None,
+            hot_module_args: args,
         })
     }
 }
 
+/// The `try_` wrappers only call the wrappers, in the static fallback they
+/// call the re-exported library functions instead.
+fn push_try_functions(
+    try_functions: impl IntoIterator<Item = syn::ItemFn>,
+    items: &mut Vec<Item>,
+    fallback_items: &mut Vec<FallbackItem>,
+) {
+    for function in try_functions {
+        fallback_items.push(FallbackItem::Item(Box::new(Item::Fn(function.clone()))));
+        items.push(Item::Fn(function));
+    }
+}
+
+/// The path of the library module defined in `file`, derived from its
+/// location below the last `src` directory, e.g. `src/systems/mod.rs` is
+/// `systems`. This assumes the default module layout without `#[path]`
+/// attributes and a library root named `lib.rs` or `main.rs`, otherwise the
+/// path has to be given with `module = "..."`.
+fn library_module_path(file: &std::path::Path, span: proc_macro2::Span) -> Vec<Ident> {
+    let components = file
+        .iter()
+        .map(|component| component.to_string_lossy().into_owned())
+        .collect::<Vec<_>>();
+    let Some(src) = components.iter().rposition(|component| component == "src") else {
+        return Vec::new();
+    };
+    let mut path = components[src + 1..].to_vec();
+    if let Some(file_name) = path.pop() {
+        match file_name.trim_end_matches(".rs") {
+            "lib" | "main" | "mod" => {}
+            module => path.push(module.to_string()),
+        }
+    }
+    path.iter().map(|module| Ident::new(module, span)).collect()
+}
+
+/// syn only parses statics without a value as verbatim items. Returns the
+/// static if `tokens` are a `#[hot_static]` declaration.
+fn parse_hot_static(tokens: &proc_macro2::TokenStream) -> Option<syn::ForeignItemStatic> {
+    match syn::parse2::<syn::ForeignItem>(tokens.clone()) {
+        Ok(syn::ForeignItem::Static(item_static))
+            if item_static
+                .attrs
+                .iter()
+                .any(|attr| attr.path().is_ident("hot_static")) =>
+        {
+            Some(item_static)
+        }
+        _ => None,
+    }
+}
+
+/// Parses the arguments of `hot_functions_from_file!` and
+/// `hot_functions_from_crate!`: a path literal, an optional
+/// `ignore_no_mangle = true`, optional `features = ["..."]`, the enabled
+/// features of the library, and an optional `module = "crate::..."`, the path
+/// of the scanned module in the library.
+fn parse_source_macro_args(
+    tokens: proc_macro2::TokenStream,
+    span: proc_macro2::Span,
+) -> Result<(LitStr, LibConfig, Option<Vec<Ident>>)> {
+    let parser = |input: syn::parse::ParseStream| {
+        let path = input
+            .parse::<LitStr>()
+            .map_err(|_| syn::Error::new(span, "expected path as a literal string"))?;
+
+        let mut config = LibConfig::new(span);
+        let mut module = None;
+        while !input.is_empty() {
+            input.parse::<token::Comma>()?;
+            if input.is_empty() {
+                break;
+            }
+            let ident = input.parse::<Ident>()?;
+            input.parse::<token::Eq>()?;
+            if ident == "ignore_no_mangle" {
+                config.ignore_no_mangle = input.parse::<LitBool>()?.value();
+            } else if ident == "features" {
+                let features;
+                syn::bracketed!(features in input);
+                let features = Punctuated::<LitStr, token::Comma>::parse_terminated(&features)?;
+                config.features = Some(features.iter().map(LitStr::value).collect());
+            } else if ident == "module" {
+                let path = input.parse::<LitStr>()?.parse::<syn::Path>()?;
+                let segments = path.segments.into_iter().map(|segment| segment.ident);
+                module = Some(segments.skip_while(|ident| ident == "crate").collect());
+            } else {
+                return Err(syn::Error::new(ident.span(), "unexpected input"));
+            }
+        }
+
+        Ok((path, config, module))
+    };
+    parser.parse2(tokens)
+}
+
 impl quote::ToTokens for HotModule {
     fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
         let Self {
             vis,
             ident,
             items,
+            method_hooks,
+            probe_symbols,
+            hot_statics,
+            hot_traits,
+            source_files,
+            fallback_items,
             hot_module_args,
             ..
         } = self;
 
-        let HotModuleAttribute {
-            lib_name,
-            lib_dir,
-            file_watch_debounce_ms,
-            crate_name,
-            loaded_lib_name_template,
-        } = match hot_module_args {
-            None => panic!("Expected to have macro attributes"),
-            Some(attributes) => attributes,
-        };
+        let args = hot_module_args;
+        let crate_name = &args.crate_name;
+
+        let lib_loader =
+            generate_lib_loader_items(ident, args, method_hooks, probe_symbols, tokens.span())
+                .expect("error generating hot lib loader helpers");
 
-        let lib_loader = generate_lib_loader_items(
-            lib_dir,
-            lib_name,
-            file_watch_debounce_ms,
-            crate_name,
-            loaded_lib_name_template,
-            tokens.span(),
-        )
-        .expect("error generating hot lib loader helpers");
-
-        let module_def = quote::quote! {
+        let hot_statics = hot_statics
+            .iter()
+            .map(|item_static| gen_hot_static_accessor(item_static, crate_name, tokens.span()));
+
+        let hot_traits = hot_traits
+            .iter()
+            .map(|ctor| gen_hot_trait_constructor(ctor, crate_name, tokens.span()));
+
+        let source_dependencies = gen_source_dependencies(source_files, tokens.span());
+
+        let mut module_def = quote::quote! {
             #vis mod #ident {
                 #( #items )*
 
+                #( #hot_statics )*
+
+                #( #hot_traits )*
+
+                #source_dependencies
+
                 #lib_loader
             }
         };
 
+        if let Some((lib, enable)) = &args.static_fallback {
+            let fallback_items =
+                gen_static_fallback_items(lib, fallback_items, crate_name, tokens.span());
+            module_def = quote::quote! {
+                #[cfg(#enable)]
+                #module_def
+
+                #[cfg(not(#enable))]
+                #vis mod #ident {
+                    #fallback_items
+
+                    #source_dependencies
+                }
+            };
+        }
+
         proc_macro2::TokenStream::extend(tokens, module_def);
     }
 }
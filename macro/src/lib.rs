@@ -1,3 +1,6 @@
+mod cfg;
+mod crate_scan;
+mod export;
 mod hot_module;
 mod util;
 
@@ -36,9 +39,23 @@ mod util;
 ///   // reads `#[unsafe(no_mangle)]` public functions from `file.rs` and generates
 ///   // forwarding functions in the context of this module that have the exact
 ///   // same signatures. Those generated functions will automatically use the
-///   // newest version of the library.
+///   // newest version of the library. Changes to the file make cargo rebuild
+///   // the module.
 ///   hot_functions_from_file!("path/to/file.rs");
 ///
+///   // Like `hot_functions_from_file!` but reads the whole library crate in the
+///   // given directory, following its `mod` declarations. For each submodule of
+///   // the library a module with the same name is generated.
+///   hot_functions_from_crate!("path/to/lib");
+///
+///   // `#[cfg(feature = "...")]` in the library is evaluated with the listed
+///   // features of the library only.
+///   hot_functions_from_file!("path/to/file.rs", features = ["reload"]);
+///
+///   // The library module defined by the file, only used by `static_fallback`.
+///   // By default it is derived from the location of the file below `src`.
+///   hot_functions_from_file!("path/to/file.rs", module = "crate::systems");
+///
 ///   // As an alternative to `hot_functions_from_file!` you can manually
 ///   // declare functions that the library should export and for which hot-reload
 ///   // implementations should be generated. It is more tedious but plays nicer
@@ -47,6 +64,9 @@ mod util;
 ///   pub fn do_stuff(arg: &str) -> u32 { /*generated*/ }
 ///
 ///   // Same as `hot_function` but as a block, multiple declarations are allowed.
+///   // With an ABI other than "Rust", e.g. `unsafe extern "C" { ... }`, the
+///   // library functions are called with that ABI and the wrappers are
+///   // `unsafe` unless declared as `safe fn`.
 ///   #[hot_functions]
 ///   extern "Rust" {
 ///       pub fn do_stuff(arg: &str) -> u32;
@@ -56,9 +76,67 @@ mod util;
 ///   // with a `#[lib_change_subscription]` attribute.
 ///    #[lib_change_subscription]
 ///    pub fn subscribe() -> hot_lib_reloader::LibReloadObserver {}
+///
+///   // The subscription function can optionally take `SubscriptionOptions` to
+///   // filter the events it receives and to bound its event queue.
+///    #[lib_change_subscription]
+///    pub fn subscribe_with(options: hot_lib_reloader::SubscriptionOptions) -> hot_lib_reloader::LibReloadObserver {}
+///
+///   // An `UpdateCursor` lets independent consumers each check if the library
+///   // was reloaded since they last looked.
+///    #[lib_update_cursor]
+///    pub fn update_cursor() -> hot_lib_reloader::UpdateCursor {}
+///
+///   // Loads the library eagerly. Returns an error instead of panicking in the
+///   // first call of a hot function, e.g. if the library directory is wrong.
+///    #[lib_init]
+///    pub fn init() -> Result<(), hot_lib_reloader::HotReloaderError> {}
+///
+///   // Returns a snapshot of the loader status: watched and loaded file, hash,
+///   // version, load time, last reload error and whether a reload is pending.
+///    #[lib_info]
+///    pub fn info() -> hot_lib_reloader::LibInfo {}
+///
+///   // Returns a guard that keeps the current library version loaded while it
+///   // is alive, e.g. to keep a `&'static str` returned by the library valid.
+///    #[lib_generation_guard]
+///    pub fn generation_guard() -> Option<hot_lib_reloader::GenerationGuard> {}
+///
+///   // Generates an accessor `SPEED()` for a `#[unsafe(no_mangle)]` static
+///   // of the library. It returns a `hot_lib_reloader::HotStatic<f32>` guard
+///   // that keeps the library version loaded while it is alive.
+///    #[hot_static]
+///    pub static SPEED: f32;
+///
+///   // Wraps the `Box<dyn Behaviour>` returned by the library function
+///   // `new_behaviour` in a `hot_lib_reloader::HotDyn` that re-creates it from
+///   // the new library version after a reload, passing it the state returned by
+///   // `Behaviour::state` of the old object.
+///    #[hot_trait(migrate = Behaviour::state)]
+///    pub fn new_behaviour(state: BehaviourState) -> hot_lib_reloader::HotDyn<dyn Behaviour> {}
 /// }
 /// ```
 ///
+/// The generated functions keep the visibility of the declared or scanned
+/// functions as well as their docs and `#[must_use]`, `#[deprecated]`,
+/// `#[track_caller]` and `#[cfg(...)]` attributes.
+///
+/// With `static_fallback = "lib", enable = cfg(feature = "reload")` the module
+/// is only hot-reloadable if the predicate holds. Otherwise it re-exports the
+/// functions of the statically linked crate `lib` and reload notifications
+/// never happen.
+///
+/// With `on_panic = "propagate" | "try" | "rollback"` the wrappers catch
+/// panics of the library functions and inform subscribers about them. `"try"`
+/// also generates a `try_` function for every hot function that returns the
+/// panic as a `hot_lib_reloader::HotPanic` error, `"rollback"` additionally
+/// restores the library version that was loaded before the panicking one.
+///
+/// With `probe = true` new library versions are loaded in a
+/// `hot-lib-reloader-probe` subprocess first and only reloaded if that
+/// succeeds. `probe_timeout_ms = 5000` sets how long the probe may take and
+/// enables it as well, so it cannot be combined with `probe = false`.
+///
 /// In case you get errors when using the macro or are generally curious, run
 /// `cargo expand` to see the generated code.
 #[proc_macro_attribute]
@@ -67,8 +145,86 @@ pub fn hot_module(
     item: proc_macro::TokenStream,
 ) -> proc_macro::TokenStream {
     let args = syn::parse_macro_input!(args as hot_module::HotModuleAttribute);
-    let mut module = syn::parse_macro_input!(item as hot_module::HotModule);
-    module.hot_module_args = Some(args);
+    // the arguments decide which wrappers are generated
+    let parser = |stream: syn::parse::ParseStream| hot_module::HotModule::parse(stream, args);
+    let module = match syn::parse::Parser::parse(parser, item) {
+        Ok(module) => module,
+        Err(err) => return err.to_compile_error().into(),
+    };
 
     (quote::quote! { #module }).into()
 }
+
+/// Use this attribute in the hot-reloadable library on an inherent `impl`
+/// block to make its public methods hot-reloadable:
+///
+/// ```ignore
+/// pub struct Counter(pub i32);
+///
+/// #[hot_lib_reloader::hot_methods]
+/// impl Counter {
+///     pub fn value(&self) -> i32 { self.0 }
+/// }
+/// ```
+///
+/// For every public method an `#[unsafe(no_mangle)]` trampoline is exported
+/// from the library. The methods themselves remain normal inherent methods.
+/// When the executable declares the impl block in a
+/// [`hot_module`](macro@hot_module) (or picks it up using
+/// `hot_functions_from_file!`), calls like `counter.value()` will dispatch to
+/// the newest library version. The first such call loads the library.
+///
+/// Generic impl blocks and generic, `async` or `const` methods are not
+/// supported.
+#[proc_macro_attribute]
+pub fn hot_methods(
+    _args: proc_macro::TokenStream,
+    item: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    let item_impl = syn::parse_macro_input!(item as syn::ItemImpl);
+    export::export_hot_methods(item_impl)
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
+
+/// Use this attribute in the hot-reloadable library to export a function.
+/// Without arguments it is equivalent to `#[unsafe(no_mangle)]`.
+///
+/// Generic functions cannot be exported by name. Instead, list the types the
+/// function should be instantiated with:
+///
+/// ```ignore
+/// #[hot_lib_reloader::hot_function(instantiate = [u32, f32, MyType])]
+/// pub fn double<T: std::ops::Add<Output = T> + Copy>(x: T) -> T {
+///     x + x
+/// }
+/// ```
+///
+/// For each listed type a monomorphized version of the function is exported.
+/// A [`hot_module`](macro@hot_module) that declares the function with the
+/// same attribute (or picks it up using `hot_functions_from_file!`) will
+/// generate a generic wrapper that can only be called with the listed types.
+/// Only functions with a single type parameter are supported.
+///
+/// Non-generic functions also get a shim that catches their panics inside of
+/// the library. A [`hot_module`](macro@hot_module) with an `on_panic` policy
+/// uses it, the executable cannot catch the panics itself unless both are
+/// linked against the same `libstd`.
+///
+/// On an `async fn` the attribute exports a function returning the boxed
+/// future of the original function. The wrapper generated by the
+/// [`hot_module`](macro@hot_module) returns a future awaiting it. With
+/// `#[hot_function(send)]` that future is `Send`, e.g. to spawn it on a
+/// multi-threaded runtime. The `hot_module` has to declare the function with
+/// the same argument.
+#[proc_macro_attribute]
+pub fn hot_function(
+    args: proc_macro::TokenStream,
+    item: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    let args = syn::parse_macro_input!(args with util::parse_hot_function_args);
+    let item_fn = syn::parse_macro_input!(item as syn::ItemFn);
+    export::export_hot_function(args, item_fn)
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
@@ -1,131 +1,213 @@
+use crate::cfg::{Cfg, LibConfig};
 use proc_macro2::Span;
-use std::path::PathBuf;
-use syn::{Error, ForeignItemFn, LitStr, Result};
-
-pub fn ident_from_pat(
-    pat: &syn::Pat,
-    func_name: &proc_macro2::Ident,
-    span: proc_macro2::Span,
-) -> syn::Result<syn::Ident> {
+use std::path::{Path, PathBuf};
+use syn::{Error, FnArg, ForeignItemFn, LitStr, Result, spanned::Spanned, visit_mut::VisitMut};
+
+/// The identifier the argument number `i` with the pattern `pat` is bound to
+/// in a generated function: the identifier of a plain `x` or `mut x` pattern,
+/// `__hot_arg{i}` for everything else.
+pub fn arg_name(i: usize, pat: &syn::Pat) -> syn::Ident {
     match pat {
-        syn::Pat::Ident(pat) => Ok(pat.ident.clone()),
-        _ => Err(syn::Error::new(
-            span,
-            format!(
-                "generating call for library function: signature of function {func_name} cannot be converted"
-            ),
-        )),
+        syn::Pat::Ident(pat) if pat.by_ref.is_none() && pat.subpat.is_none() => pat.ident.clone(),
+        _ => syn::Ident::new(&format!("__hot_arg{i}"), pat.span()),
     }
 }
 
+/// Items found by [`read_functions_from_file`].
+pub struct FileExports {
+    pub functions: Vec<(ForeignItemFn, Span)>,
+    /// impl blocks annotated with `#[hot_methods]`
+    pub method_impls: Vec<(syn::ItemImpl, Span)>,
+    /// functions annotated with `#[hot_function(instantiate = [...])]`
+    pub generic_functions: Vec<(GenericFunction, Span)>,
+}
+
 /// Reads the contents of a Rust source file and finds the top-level functions that have
 /// - visibility public
 /// - #[unsafe(no_mangle)] attribute
 ///
 /// It converts these functions into a [syn::ForeignItemFn] so that those can
 /// serve as lib function declarations of the lib reloader.
+///
+/// Top-level impl blocks with a `#[hot_methods]` attribute and generic
+/// functions with a `#[hot_function(instantiate = [...])]` attribute are
+/// collected as well.
+///
+/// The path is resolved with [`resolve_source_path`], the resolved path is
+/// returned along with the exports.
 pub fn read_functions_from_file(
     file_name: LitStr,
-    ignore_no_mangle: bool,
-) -> Result<Vec<(ForeignItemFn, Span)>> {
+    config: &LibConfig,
+) -> Result<(PathBuf, FileExports)> {
     let span = file_name.span();
-    let path: PathBuf = file_name.value().into();
+    let path = resolve_source_path(&file_name, "Rust source file", |path| path.is_file())?;
 
-    if !path.exists() {
-        return Err(Error::new(
-            span,
-            format!(
-                "Could not find Rust source file {path:?}. Please make sure that you specify the file path from the project root directory. Please not that this has been changed in hot-lib-reloader v0.5 -> v0.6. See https://github.com/rksm/hot-lib-reloader-rs/issues/13."
-            ),
-        ));
+    let ast = parse_source_file(&path, span)?;
+    Ok((path, collect_exports(ast.items, span, config)?))
+}
+
+/// Resolves a path given to `hot_functions_from_file!` or
+/// `hot_functions_from_crate!`. Relative paths are tried against
+/// - the directory of the invoking crate (`CARGO_MANIFEST_DIR`)
+/// - the root of its workspace
+/// - the current directory of the compiler
+///
+/// A leading `$CARGO_MANIFEST_DIR` is replaced with the crate directory.
+/// Returns the first candidate for which `found` is true, otherwise an error
+/// listing all candidates.
+pub fn resolve_source_path(
+    path: &LitStr,
+    what: &str,
+    found: impl Fn(&Path) -> bool,
+) -> Result<PathBuf> {
+    let value = path.value();
+    let manifest_dir = std::env::var_os("CARGO_MANIFEST_DIR").map(PathBuf::from);
+
+    let mut candidates = Vec::new();
+    let prefixed = ["$CARGO_MANIFEST_DIR", "${CARGO_MANIFEST_DIR}"]
+        .iter()
+        .find_map(|prefix| value.strip_prefix(prefix));
+    match (prefixed, &manifest_dir) {
+        (Some(rest), Some(manifest_dir)) => {
+            candidates.push(manifest_dir.join(rest.trim_start_matches(['/', '\\'])));
+        }
+        (Some(_), None) => {
+            return Err(Error::new(
+                path.span(),
+                "CARGO_MANIFEST_DIR is not set, paths starting with $CARGO_MANIFEST_DIR require building with cargo",
+            ));
+        }
+        (None, _) if Path::new(&value).is_absolute() => candidates.push(PathBuf::from(&value)),
+        (None, _) => {
+            if let Some(manifest_dir) = &manifest_dir {
+                candidates.push(manifest_dir.join(&value));
+                if let Some(workspace_root) = find_workspace_root(manifest_dir) {
+                    candidates.push(workspace_root.join(&value));
+                }
+            }
+            if let Ok(current_dir) = std::env::current_dir() {
+                candidates.push(current_dir.join(&value));
+            }
+        }
+    }
+    candidates.dedup();
+
+    if let Some(found) = candidates.iter().find(|candidate| found(candidate)) {
+        return Ok(found.clone());
     }
+    let tried = candidates
+        .iter()
+        .map(|candidate| format!("{candidate:?}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    Err(Error::new(
+        path.span(),
+        format!(
+            "Could not find {what} {value:?}, tried {tried}. Relative paths are resolved against the directory of the crate (CARGO_MANIFEST_DIR) and the workspace root."
+        ),
+    ))
+}
+
+/// The closest ancestor of `manifest_dir` with a `Cargo.toml` declaring a
+/// `[workspace]`, if any.
+fn find_workspace_root(manifest_dir: &Path) -> Option<PathBuf> {
+    manifest_dir
+        .ancestors()
+        .skip(1)
+        .find(|dir| {
+            std::fs::read_to_string(dir.join("Cargo.toml"))
+                .is_ok_and(|manifest| manifest.lines().any(|line| line.trim() == "[workspace]"))
+        })
+        .map(Path::to_path_buf)
+}
 
-    let content = std::fs::read_to_string(&path)
+/// Reads and parses the Rust source file at `path`.
+pub fn parse_source_file(path: &std::path::Path, span: Span) -> Result<syn::File> {
+    let content = std::fs::read_to_string(path)
         .map_err(|err| Error::new(span, format!("Error reading file {path:?}: {err}")))?;
 
-    let ast = syn::parse_file(&content)?;
+    syn::parse_file(&content)
+        .map_err(|err| Error::new(span, format!("Error parsing file {path:?}: {err}")))
+}
 
+/// Finds the exported functions, `#[hot_methods]` impl blocks and generic
+/// functions in `items`, see [`read_functions_from_file`].
+pub fn collect_exports(
+    items: impl IntoIterator<Item = syn::Item>,
+    span: Span,
+    config: &LibConfig,
+) -> Result<FileExports> {
     let mut functions = Vec::new();
+    let mut method_impls = Vec::new();
+    let mut generic_functions = Vec::new();
 
-    for item in ast.items {
+    for item in items {
         match item {
+            syn::Item::Impl(mut item_impl) if item_impl.attrs.iter().any(is_hot_methods_attr) => {
+                let cfg = Cfg::of_item(&item_impl.attrs, config)?;
+                if matches!(cfg, Cfg::Disabled) {
+                    continue;
+                }
+                // the wrappers only get the predicates that are left undecided
+                item_impl.attrs.retain(|attr| !attr.path().is_ident("cfg"));
+                item_impl.attrs.extend(cfg.attr());
+                method_impls.push((item_impl, span));
+            }
+
             syn::Item::Fn(fun) => {
+                let cfg = Cfg::of_item(&fun.attrs, config)?;
+                if matches!(cfg, Cfg::Disabled) {
+                    continue;
+                }
                 match fun.vis {
                     syn::Visibility::Public(_) => {}
                     _ => continue,
                 };
 
-                // we can optionally assume that the function will be unmangled
-                // by other means than a direct attribute
-                if !ignore_no_mangle {
-                    fn cfg_no_mangle<'a>(
-                        mut cfg_items: impl Iterator<Item = &'a syn::Meta>,
-                    ) -> bool {
-                        let _predicate = cfg_items.next();
-                        // TODO: return false if predicate is false
-                        // false positives are unlikely, but can still compile error
-                        cfg_items.any(|meta| match meta {
-                            syn::Meta::Path(path) => path.is_ident("no_mangle"),
-                            syn::Meta::List(list) => {
-                                let mut found_no_mangle = false;
-                                if list
-                                    .parse_nested_meta(|meta| {
-                                        if meta.path.is_ident("no_mangle") {
-                                            found_no_mangle = true;
-                                        }
-                                        Ok(())
-                                    })
-                                    .is_err()
-                                {
-                                    return false;
-                                }
-                                found_no_mangle
-                            }
-
-                            _ => false,
-                        })
-                    }
+                // generic functions exported with an instantiation list
+                let hot_function_attr = fun.attrs.iter().find(|attr| is_hot_function_attr(attr));
+                if let Some(instantiations) = hot_function_attr
+                    .map(parse_hot_function_attr)
+                    .transpose()?
+                    .and_then(|args| args.instantiations)
+                {
+                    let mut generic = GenericFunction::new(fun.sig, instantiations)?;
+                    generic.attrs = forwarded_attrs(&fun.attrs)
+                        .filter(|attr| !attr.path().is_ident("cfg"))
+                        .cloned()
+                        .chain(cfg.attr())
+                        .collect();
+                    generic_functions.push((generic, span));
+                    continue;
+                }
 
-                    fn is_no_mangle<'a>(
-                        mut attrs: impl Iterator<Item = &'a syn::Attribute>,
-                    ) -> bool {
-                        attrs.any(|attr| {
-                            let ident = match attr.path().get_ident() {
-                                Some(i) => i,
-                                None => return false,
-                            };
-                            if *ident == "no_mangle" {
-                                true
-                            } else if *ident == "unsafe" {
-                                let mut found_no_mangle = false;
-                                if attr.parse_nested_meta(|meta| {
-                                    if meta.path.is_ident("no_mangle") {
-                                        found_no_mangle = true;
-                                    }
-                                    Ok(())
-                                }).is_err() {
-                                    return false;
-                                }
-                                found_no_mangle
-                            } else if *ident == "cfg_attr" {
-                                let nested = match attr.parse_args_with(syn::punctuated::Punctuated::<syn::Meta, syn::Token![,]>::parse_terminated) {
-                                    Ok(nested) => nested,
-                                    _ => return false,
-                                };
-                                cfg_no_mangle(nested.iter())
-                            } else {
-                                false
-                            }
-                        })
-                    }
+                // async functions are only exported through the shim generated
+                // by `#[hot_function]`
+                if fun.sig.asyncness.is_some() && hot_function_attr.is_none() {
+                    continue;
+                }
 
-                    if !is_no_mangle(fun.attrs.iter()) {
-                        continue;
-                    };
+                // we can optionally assume that the function will be unmangled
+                // by other means than a direct attribute
+                let cfg = if config.ignore_no_mangle {
+                    cfg
+                } else {
+                    cfg.and(no_mangle_cfg(&fun.attrs, config)?)
+                };
+                if matches!(cfg, Cfg::Disabled) {
+                    continue;
                 }
 
+                // `#[cfg]` is replaced with the predicates left undecided, the
+                // arguments of `#[hot_function]` are needed for async functions
+                let attrs = forwarded_attrs(&fun.attrs)
+                    .filter(|attr| !attr.path().is_ident("cfg"))
+                    .cloned()
+                    .chain(cfg.attr())
+                    .chain(hot_function_attr.cloned())
+                    .collect();
                 let fun = ForeignItemFn {
-                    attrs: Vec::new(),
+                    attrs,
                     vis: fun.vis,
                     sig: fun.sig,
                     semi_token: syn::token::Semi(span),
@@ -137,5 +219,673 @@ pub fn read_functions_from_file(
         }
     }
 
-    Ok(functions)
+    Ok(FileExports {
+        functions,
+        method_impls,
+        generic_functions,
+    })
+}
+
+/// Whether the attributes unmangle the function name: `#[unsafe(no_mangle)]`,
+/// `#[hot_function]` or `#[cfg_attr(predicate, unsafe(no_mangle))]` with the
+/// predicate evaluated. [`Cfg::Disabled`] if the function is not unmangled.
+fn no_mangle_cfg(attrs: &[syn::Attribute], config: &LibConfig) -> Result<Cfg> {
+    fn contains_no_mangle(meta: &syn::Meta) -> bool {
+        match meta {
+            syn::Meta::Path(path) => path.is_ident("no_mangle"),
+            syn::Meta::List(list) if list.path.is_ident("unsafe") => {
+                let mut found_no_mangle = false;
+                list.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("no_mangle") {
+                        found_no_mangle = true;
+                    }
+                    Ok(())
+                })
+                .is_ok()
+                    && found_no_mangle
+            }
+            _ => false,
+        }
+    }
+
+    let mut result = Cfg::Disabled;
+    for attr in attrs {
+        // `#[hot_function]` adds `no_mangle` in the library
+        if is_hot_function_attr(attr) || contains_no_mangle(&attr.meta) {
+            return Ok(Cfg::Enabled);
+        }
+        if !attr.path().is_ident("cfg_attr") {
+            continue;
+        }
+        let nested = attr.parse_args_with(
+            syn::punctuated::Punctuated::<syn::Meta, syn::Token![,]>::parse_terminated,
+        )?;
+        let mut nested = nested.iter();
+        let Some(predicate) = nested.next() else {
+            continue;
+        };
+        if !nested.any(contains_no_mangle) {
+            continue;
+        }
+        match Cfg::of_predicate(predicate, config)? {
+            Cfg::Enabled => return Ok(Cfg::Enabled),
+            Cfg::Disabled => {}
+            unknown => result = unknown,
+        }
+    }
+    Ok(result)
+}
+
+/// Matches `#[hot_methods]` as well as paths like `#[hot_lib_reloader::hot_methods]`.
+pub fn is_hot_methods_attr(attr: &syn::Attribute) -> bool {
+    attr.path()
+        .segments
+        .last()
+        .is_some_and(|segment| segment.ident == "hot_methods")
+}
+
+/// Name of the `#[unsafe(no_mangle)]` trampoline that the library exports for
+/// method `method` of `self_ty`.
+pub fn method_symbol_name(self_ty: &syn::Type, method: &syn::Ident) -> Result<syn::Ident> {
+    let ty_ident = match self_ty {
+        syn::Type::Path(syn::TypePath { path, qself: None }) => path.segments.last(),
+        _ => None,
+    }
+    .filter(|segment| segment.arguments.is_none())
+    .map(|segment| &segment.ident)
+    .ok_or_else(|| {
+        Error::new(
+            self_ty.span(),
+            "hot methods are only supported for impl blocks of non-generic named types",
+        )
+    })?;
+    Ok(syn::Ident::new(
+        &format!("__hot_method__{ty_ident}__{method}"),
+        method.span(),
+    ))
+}
+
+/// A method signature turned into a free function signature: the receiver
+/// becomes the first argument and `Self` is replaced by the concrete type.
+pub struct MethodSignature {
+    /// Argument names `__hot_arg0`, `__hot_arg1`, ... including the receiver.
+    pub arg_names: Vec<syn::Ident>,
+    /// `fn(...) -> ...` pointer type of the free function.
+    pub fn_ptr: syn::TypeBareFn,
+    /// The signature of the free function named `ident`.
+    pub free_fn_sig: syn::Signature,
+}
+
+pub fn method_signature(
+    self_ty: &syn::Type,
+    sig: &syn::Signature,
+    ident: syn::Ident,
+) -> Result<MethodSignature> {
+    let err = |msg: &str| {
+        Err(Error::new(
+            sig.span(),
+            format!("hot method {}: {msg}", sig.ident),
+        ))
+    };
+    if sig.asyncness.is_some() {
+        return err("async methods are not supported");
+    }
+    if sig.constness.is_some() {
+        return err("const methods are not supported");
+    }
+    if sig.variadic.is_some() {
+        return err("variadic methods are not supported");
+    }
+    if sig.generics.where_clause.is_some()
+        || sig
+            .generics
+            .params
+            .iter()
+            .any(|param| !matches!(param, syn::GenericParam::Lifetime(_)))
+    {
+        return err("generic methods are not supported");
+    }
+
+    let mut arg_names = Vec::new();
+    let mut arg_types = Vec::new();
+    for (i, arg) in sig.inputs.iter().enumerate() {
+        let ty = match arg {
+            FnArg::Receiver(receiver) => (*receiver.ty).clone(),
+            FnArg::Typed(typed) => (*typed.ty).clone(),
+        };
+        if matches!(ty, syn::Type::ImplTrait(_)) {
+            return err("`impl Trait` arguments are not supported");
+        }
+        arg_names.push(syn::Ident::new(&format!("__hot_arg{i}"), arg.span()));
+        arg_types.push(ty);
+    }
+
+    let mut output = sig.output.clone();
+    let mut replace_self = ReplaceType("Self", self_ty);
+    for ty in &mut arg_types {
+        replace_self.visit_type_mut(ty);
+    }
+    replace_self.visit_return_type_mut(&mut output);
+
+    let lifetimes = sig
+        .generics
+        .lifetimes()
+        .map(|param| param.lifetime.clone())
+        .collect::<Vec<_>>();
+    let unsafety = &sig.unsafety;
+    let fn_ptr: syn::TypeBareFn = if lifetimes.is_empty() {
+        syn::parse_quote! { #unsafety fn( #( #arg_types ),* ) #output }
+    } else {
+        syn::parse_quote! { for< #( #lifetimes ),* > #unsafety fn( #( #arg_types ),* ) #output }
+    };
+
+    let free_fn_sig = syn::Signature {
+        ident,
+        inputs: arg_names
+            .iter()
+            .zip(&arg_types)
+            .map(|(name, ty)| -> FnArg {
+                syn::parse_quote! { #name: #ty }
+            })
+            .collect(),
+        output,
+        ..sig.clone()
+    };
+
+    Ok(MethodSignature {
+        arg_names,
+        fn_ptr,
+        free_fn_sig,
+    })
+}
+
+/// The signature of a hot function wrapper, see [`wrapper_signature`].
+pub struct WrapperSignature {
+    /// The signature of the wrapper, every argument is bound to a plain
+    /// identifier.
+    pub sig: syn::Signature,
+    /// The identifiers the arguments are bound to.
+    pub arg_names: Vec<syn::Ident>,
+    /// `fn(...) -> ...` pointer type of the library function. It refers to
+    /// the lifetime parameters of the wrapper so it is only valid inside of
+    /// the wrapper.
+    pub fn_ptr: syn::TypeBareFn,
+}
+
+/// Prepares the signature of the library function `sig` for a wrapper that
+/// forwards its arguments to the library. Arguments with patterns like `_`,
+/// `(a, b): (i32, i32)` or `mut x` are bound to plain identifiers, `const` is
+/// dropped as the wrapper calls into the library at runtime.
+pub fn wrapper_signature(sig: syn::Signature) -> Result<WrapperSignature> {
+    let err = |msg: &str| {
+        Err(Error::new(
+            sig.span(),
+            format!("hot function {}: {msg}", sig.ident),
+        ))
+    };
+    if sig.asyncness.is_some() {
+        return err("async functions are not supported");
+    }
+    if sig.variadic.is_some() {
+        return err("variadic functions are not supported");
+    }
+    if sig
+        .generics
+        .params
+        .iter()
+        .any(|param| !matches!(param, syn::GenericParam::Lifetime(_)))
+    {
+        return err(
+            "generic functions cannot be exported as is, declare them with #[hot_function(instantiate = [...])]",
+        );
+    }
+    if let Some(span) = find_impl_trait(&sig) {
+        return Err(Error::new(
+            span,
+            format!(
+                "hot function {}: `impl Trait` makes the function generic so it cannot be exported, use `&dyn Trait` or `Box<dyn Trait>` instead",
+                sig.ident
+            ),
+        ));
+    }
+
+    let mut sig = sig;
+    sig.constness = None;
+    // the wrapper is a Rust function, only the library function has the ABI
+    let abi = sig.abi.take();
+
+    let mut arg_names = Vec::new();
+    let mut arg_types = Vec::new();
+    for (i, arg) in sig.inputs.iter_mut().enumerate() {
+        let typed = match arg {
+            FnArg::Typed(typed) => typed,
+            FnArg::Receiver(receiver) => {
+                return Err(Error::new(
+                    receiver.span(),
+                    "`self` arguments are not supported, use #[hot_methods] for methods",
+                ));
+            }
+        };
+        let name = arg_name(i, &typed.pat);
+        typed.pat = syn::parse_quote! { #name };
+        arg_names.push(name);
+        arg_types.push((*typed.ty).clone());
+    }
+
+    let unsafety = &sig.unsafety;
+    let output = &sig.output;
+    let fn_ptr = syn::parse_quote! { #unsafety #abi fn( #( #arg_types ),* ) #output };
+
+    Ok(WrapperSignature {
+        sig,
+        arg_names,
+        fn_ptr,
+    })
+}
+
+/// Name of the lifetime the future of an async hot function is bound to.
+const FUTURE_LIFETIME: &str = "'__hot_future";
+
+/// For an `async fn` returns the signature of the synchronous function the
+/// library exports for it:
+///
+/// ```ignore
+/// pub async fn handle<'a>(req: &Request, name: &'a str) -> Response;
+/// // becomes
+/// pub fn __hot_async__handle<'__hot_future, 'a>(
+///     req: &'__hot_future Request,
+///     name: &'a str,
+/// ) -> Pin<Box<dyn Future<Output = Response> + '__hot_future>>
+/// where
+///     'a: '__hot_future;
+/// ```
+///
+/// Elided lifetimes are bound to the lifetime of the future. With `send` (see
+/// [`HotFunctionArgs::send`]) the future is `Send + '__hot_future`.
+pub fn async_shim_signature(sig: &syn::Signature, send: bool) -> Result<syn::Signature> {
+    let future_lifetime = syn::Lifetime::new(FUTURE_LIFETIME, Span::call_site());
+    let output = async_output(sig, &future_lifetime)?;
+    let send = send.then(|| quote::quote! { + Send });
+
+    let mut shim = sig.clone();
+    shim.asyncness = None;
+    shim.ident = async_symbol_name(&sig.ident);
+    shim.output = syn::parse_quote! {
+        -> ::std::pin::Pin<Box<dyn ::std::future::Future<Output = #output> #send + #future_lifetime>>
+    };
+    bind_elided_lifetimes(&mut shim, future_lifetime);
+    Ok(shim)
+}
+
+/// The signature of the wrapper of an async hot function in the `hot_module`:
+/// like [`async_shim_signature`] but named like the function and returning
+/// an `impl Future`.
+pub fn async_wrapper_signature(sig: &syn::Signature, send: bool) -> Result<syn::Signature> {
+    let future_lifetime = syn::Lifetime::new(FUTURE_LIFETIME, Span::call_site());
+    let output = async_output(sig, &future_lifetime)?;
+    let send = send.then(|| quote::quote! { + Send });
+
+    let mut wrapper = sig.clone();
+    wrapper.asyncness = None;
+    wrapper.output = syn::parse_quote! {
+        -> impl ::std::future::Future<Output = #output> #send + #future_lifetime
+    };
+    bind_elided_lifetimes(&mut wrapper, future_lifetime);
+    Ok(wrapper)
+}
+
+/// Name of the symbol of the shim `#[hot_function]` exports for `ident` that
+/// catches panics inside of the library.
+pub fn catch_symbol_name(ident: &syn::Ident) -> syn::Ident {
+    syn::Ident::new(&format!("__hot_catch__{ident}"), ident.span())
+}
+
+/// The result type of the shim named by [`catch_symbol_name`], the error is
+/// the panic message.
+pub fn catch_output(output: &syn::ReturnType) -> syn::ReturnType {
+    let output = match output {
+        syn::ReturnType::Default => syn::parse_quote! { () },
+        syn::ReturnType::Type(_, ty) => (**ty).clone(),
+    };
+    syn::parse_quote! {
+        -> ::std::result::Result<#output, ::std::option::Option<::std::string::String>>
+    }
+}
+
+/// Name of the symbol the library exports for the async function `ident`.
+pub fn async_symbol_name(ident: &syn::Ident) -> syn::Ident {
+    syn::Ident::new(&format!("__hot_async__{ident}"), ident.span())
+}
+
+/// The output type of the async function `sig`.
+fn async_output(sig: &syn::Signature, future_lifetime: &syn::Lifetime) -> Result<syn::Type> {
+    if sig
+        .generics
+        .lifetimes()
+        .any(|param| param.lifetime == *future_lifetime)
+    {
+        return Err(Error::new(
+            sig.span(),
+            format!(
+                "hot function {}: lifetime {future_lifetime} is reserved",
+                sig.ident
+            ),
+        ));
+    }
+    let mut output = match &sig.output {
+        syn::ReturnType::Default => syn::parse_quote! { () },
+        syn::ReturnType::Type(_, ty) => (**ty).clone(),
+    };
+    BindElidedLifetimes(future_lifetime).visit_type_mut(&mut output);
+    Ok(output)
+}
+
+/// Adds the lifetime of the future to `sig`, binds elided lifetimes of the
+/// arguments to it and requires named lifetimes to outlive it.
+fn bind_elided_lifetimes(sig: &mut syn::Signature, future_lifetime: syn::Lifetime) {
+    let named = sig
+        .generics
+        .lifetimes()
+        .map(|param| param.lifetime.clone())
+        .collect::<Vec<_>>();
+    for arg in &mut sig.inputs {
+        if let FnArg::Typed(typed) = arg {
+            BindElidedLifetimes(&future_lifetime).visit_type_mut(&mut typed.ty);
+        }
+    }
+    let where_clause = sig.generics.make_where_clause();
+    for lifetime in named {
+        where_clause
+            .predicates
+            .push(syn::parse_quote! { #lifetime: #future_lifetime });
+    }
+    sig.generics
+        .params
+        .insert(0, syn::parse_quote! { #future_lifetime });
+}
+
+/// Replaces elided lifetimes (`&T`, `'_`) with `.0`. Lifetimes elided in
+/// `fn(&T)` pointers and `Fn(&T)` bounds are higher-ranked and kept.
+struct BindElidedLifetimes<'a>(&'a syn::Lifetime);
+
+impl VisitMut for BindElidedLifetimes<'_> {
+    fn visit_type_reference_mut(&mut self, reference: &mut syn::TypeReference) {
+        if reference.lifetime.is_none() {
+            reference.lifetime = Some(self.0.clone());
+        }
+        syn::visit_mut::visit_type_reference_mut(self, reference);
+    }
+
+    fn visit_lifetime_mut(&mut self, lifetime: &mut syn::Lifetime) {
+        if lifetime.ident == "_" {
+            *lifetime = self.0.clone();
+        }
+    }
+
+    fn visit_type_bare_fn_mut(&mut self, _: &mut syn::TypeBareFn) {}
+
+    fn visit_parenthesized_generic_arguments_mut(
+        &mut self,
+        _: &mut syn::ParenthesizedGenericArguments,
+    ) {
+    }
+}
+
+/// Turns a function declared in an `extern "ABI" { ... }` block into a
+/// function declaration with that ABI. Like foreign functions, functions
+/// declared with a non-Rust ABI are `unsafe` to call unless they are declared
+/// as `safe fn`. Returns `None` for items that are not functions.
+pub fn foreign_fn_with_abi(
+    item: syn::ForeignItem,
+    abi: &syn::Abi,
+) -> Result<Option<ForeignItemFn>> {
+    let (mut f, safe) = match item {
+        syn::ForeignItem::Fn(f) => (f, false),
+        // syn does not parse `safe fn`, drop the `safe` and parse again
+        syn::ForeignItem::Verbatim(tokens) => {
+            let mut found_safe = false;
+            let tokens = tokens
+                .into_iter()
+                .filter(|token| match token {
+                    proc_macro2::TokenTree::Ident(ident) if ident == "safe" && !found_safe => {
+                        found_safe = true;
+                        false
+                    }
+                    _ => true,
+                })
+                .collect::<proc_macro2::TokenStream>();
+            match syn::parse2::<ForeignItemFn>(tokens) {
+                Ok(f) if found_safe => (f, true),
+                _ => return Ok(None),
+            }
+        }
+        _ => return Ok(None),
+    };
+
+    let is_rust_abi = abi.name.as_ref().is_some_and(|name| name.value() == "Rust");
+    if !is_rust_abi && !safe {
+        f.sig.unsafety.get_or_insert_with(Default::default);
+    }
+    f.sig.abi = Some(abi.clone());
+    Ok(Some(f))
+}
+
+/// Finds `impl Trait` in argument or return position of `sig`.
+fn find_impl_trait(sig: &syn::Signature) -> Option<Span> {
+    struct FindImplTrait(Option<Span>);
+
+    impl<'ast> syn::visit::Visit<'ast> for FindImplTrait {
+        fn visit_type_impl_trait(&mut self, impl_trait: &'ast syn::TypeImplTrait) {
+            self.0.get_or_insert(impl_trait.impl_token.span);
+        }
+    }
+
+    let mut find = FindImplTrait(None);
+    syn::visit::Visit::visit_signature(&mut find, sig);
+    find.0
+}
+
+/// Replaces all occurrences of the type named `.0` with the type `.1`.
+pub struct ReplaceType<'a, I: ?Sized>(pub &'a I, pub &'a syn::Type);
+
+impl<I> VisitMut for ReplaceType<'_, I>
+where
+    I: ?Sized,
+    syn::Ident: PartialEq<I>,
+{
+    fn visit_type_mut(&mut self, ty: &mut syn::Type) {
+        if let syn::Type::Path(syn::TypePath { qself: None, path }) = ty
+            && path.is_ident(self.0)
+        {
+            *ty = self.1.clone();
+            return;
+        }
+        syn::visit_mut::visit_type_mut(self, ty);
+    }
+}
+
+/// Arguments of `#[hot_function(...)]`.
+#[derive(Default)]
+pub struct HotFunctionArgs {
+    /// `instantiate = [u32, f32, MyType]`
+    pub instantiations: Option<Vec<syn::Type>>,
+    /// `send`: the future of an async function is `Send`.
+    pub send: Option<syn::Ident>,
+}
+
+/// Parses the arguments of `#[hot_function(instantiate = [u32, f32, MyType])]`
+/// or `#[hot_function(send)]`. Returns the defaults for a plain
+/// `#[hot_function]`.
+pub fn parse_hot_function_attr(attr: &syn::Attribute) -> Result<HotFunctionArgs> {
+    if matches!(attr.meta, syn::Meta::Path(_)) {
+        return Ok(HotFunctionArgs::default());
+    }
+    attr.parse_args_with(parse_hot_function_args)
+}
+
+/// The arguments of the `#[hot_function]` attribute in `attrs`, if any.
+pub fn hot_function_args(attrs: &[syn::Attribute]) -> Result<HotFunctionArgs> {
+    attrs
+        .iter()
+        .find(|attr| is_hot_function_attr(attr))
+        .map(parse_hot_function_attr)
+        .transpose()
+        .map(Option::unwrap_or_default)
+}
+
+/// Parses `instantiate = [u32, f32, MyType]`, `send` or nothing.
+pub fn parse_hot_function_args(input: syn::parse::ParseStream) -> Result<HotFunctionArgs> {
+    let mut args = HotFunctionArgs::default();
+    while !input.is_empty() {
+        let ident = input.parse::<syn::Ident>()?;
+        if ident == "send" {
+            args.send = Some(ident);
+        } else if ident == "instantiate" {
+            input.parse::<syn::Token![=]>()?;
+            let types;
+            syn::bracketed!(types in input);
+            let types =
+                syn::punctuated::Punctuated::<syn::Type, syn::Token![,]>::parse_terminated(&types)?;
+            if types.is_empty() {
+                return Err(Error::new(ident.span(), "instantiation list is empty"));
+            }
+            args.instantiations = Some(types.into_iter().collect());
+        } else {
+            return Err(Error::new(
+                ident.span(),
+                "expected `instantiate = [...]` or `send`",
+            ));
+        }
+        if !input.is_empty() {
+            input.parse::<syn::Token![,]>()?;
+        }
+    }
+    Ok(args)
+}
+
+/// Attributes of a library function that are forwarded to its wrapper in the
+/// hot module, so that docs and lints are the same as for the static build.
+pub fn forwarded_attrs(attrs: &[syn::Attribute]) -> impl Iterator<Item = &syn::Attribute> {
+    const FORWARDED: &[&str] = &["doc", "must_use", "deprecated", "track_caller", "cfg"];
+    attrs
+        .iter()
+        .filter(|attr| FORWARDED.iter().any(|name| attr.path().is_ident(name)))
+}
+
+/// Matches `#[hot_function]` as well as paths like `#[hot_lib_reloader::hot_function]`.
+pub fn is_hot_function_attr(attr: &syn::Attribute) -> bool {
+    attr.path()
+        .segments
+        .last()
+        .is_some_and(|segment| segment.ident == "hot_function")
+}
+
+/// A generic hot function with a single type parameter together with the
+/// types it is instantiated for.
+pub struct GenericFunction {
+    pub sig: syn::Signature,
+    pub type_param: syn::Ident,
+    pub instantiations: Vec<syn::Type>,
+    /// attributes forwarded to the generic wrapper, `#[cfg(...)]` applies to
+    /// all generated items
+    pub attrs: Vec<syn::Attribute>,
+}
+
+impl GenericFunction {
+    pub fn new(sig: syn::Signature, instantiations: Vec<syn::Type>) -> Result<Self> {
+        let err = |msg: &str| {
+            Err(Error::new(
+                sig.span(),
+                format!("hot function {}: {msg}", sig.ident),
+            ))
+        };
+        let mut type_params = sig.generics.type_params();
+        let type_param = match (type_params.next(), type_params.next()) {
+            (Some(param), None) => param.ident.clone(),
+            _ => return err("instantiation lists require exactly one type parameter"),
+        };
+        if sig.generics.const_params().next().is_some() {
+            return err("const generics are not supported");
+        }
+        if sig.asyncness.is_some() || sig.variadic.is_some() {
+            return err("async and variadic functions are not supported");
+        }
+        if sig.inputs.iter().any(|arg| match arg {
+            FnArg::Receiver(_) => true,
+            FnArg::Typed(typed) => matches!(*typed.ty, syn::Type::ImplTrait(_)),
+        }) {
+            return err("`self` and `impl Trait` arguments are not supported");
+        }
+        Ok(Self {
+            sig,
+            type_param,
+            instantiations,
+            attrs: Vec::new(),
+        })
+    }
+
+    /// Name of the exported symbol for the instantiation with `ty`. Different
+    /// types get different names: alphanumeric characters are kept, spaces
+    /// between tokens become `__` and other characters are escaped as `_` + hex
+    /// code + `_`, e.g. `Vec<u8>` becomes `Vec___3c___u8___3e_`.
+    pub fn symbol_name(&self, ty: &syn::Type) -> syn::Ident {
+        let ty = quote::ToTokens::to_token_stream(ty)
+            .to_string()
+            .chars()
+            .map(|c| match c {
+                c if c.is_ascii_alphanumeric() => c.to_string(),
+                ' ' => "__".to_string(),
+                c => format!("_{:x}_", c as u32),
+            })
+            .collect::<String>();
+        syn::Ident::new(
+            &format!("__hot_generic__{}__{ty}", self.sig.ident),
+            self.sig.ident.span(),
+        )
+    }
+
+    /// The signature of the instantiation for `ty`, named like the exported
+    /// symbol and with arguments named `__hot_arg0`, `__hot_arg1`, ...
+    pub fn instantiated_sig(&self, ty: &syn::Type) -> syn::Signature {
+        let mut sig = self.sig.clone();
+        sig.ident = self.symbol_name(ty);
+        let params = std::mem::take(&mut sig.generics.params);
+        sig.generics.params = params
+            .into_iter()
+            .filter(|param| matches!(param, syn::GenericParam::Lifetime(_)))
+            .collect();
+        let mut replace = ReplaceType(&self.type_param, ty);
+        if let Some(where_clause) = &mut sig.generics.where_clause {
+            replace.visit_where_clause_mut(where_clause);
+        }
+        for (i, arg) in sig.inputs.iter_mut().enumerate() {
+            if let FnArg::Typed(typed) = arg {
+                let name = Self::arg_name(i);
+                typed.pat = syn::parse_quote! { #name };
+                replace.visit_type_mut(&mut typed.ty);
+            }
+        }
+        replace.visit_return_type_mut(&mut sig.output);
+        sig
+    }
+
+    pub fn arg_names(&self) -> Vec<syn::Ident> {
+        (0..self.sig.inputs.len()).map(Self::arg_name).collect()
+    }
+
+    fn arg_name(i: usize) -> syn::Ident {
+        syn::Ident::new(&format!("__hot_arg{i}"), Span::call_site())
+    }
+}
+
+/// Wraps `expr` in an `unsafe` block if `unsafety` is set, e.g. to call an
+/// `unsafe fn`.
+pub fn unsafe_if(
+    unsafety: &Option<syn::Token![unsafe]>,
+    expr: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    match unsafety {
+        Some(_) => quote::quote! { unsafe { #expr } },
+        None => expr,
+    }
 }
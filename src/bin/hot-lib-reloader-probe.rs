@@ -0,0 +1,52 @@
+//! Loads a library in a separate process so that a broken build crashes this
+//! process instead of the one hot-reloading it, see `LibReloader::set_probe`.
+//!
+//! ```text
+//! hot-lib-reloader-probe <library file> [symbol...]
+//! ```
+//!
+//! Exits successfully if the library can be loaded with all its symbols bound
+//! immediately and it exports all the given symbols.
+
+use std::ffi::OsStr;
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    let mut args = std::env::args_os().skip(1);
+    let Some(lib_file) = args.next() else {
+        eprintln!("usage: hot-lib-reloader-probe <library file> [symbol...]");
+        return ExitCode::from(2);
+    };
+
+    let lib = match load_library(&lib_file) {
+        Ok(lib) => lib,
+        Err(err) => {
+            eprintln!("cannot load {lib_file:?}: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let missing = args
+        .filter(|symbol| unsafe { lib.get::<*const ()>(symbol.as_encoded_bytes()) }.is_err())
+        .collect::<Vec<_>>();
+    if !missing.is_empty() {
+        eprintln!("{lib_file:?} does not export {missing:?}");
+        return ExitCode::FAILURE;
+    }
+
+    // unloading is not part of the check
+    std::mem::forget(lib);
+    ExitCode::SUCCESS
+}
+
+/// Unlike `libloading::Library::new` resolves all undefined symbols right away.
+#[cfg(unix)]
+fn load_library(lib_file: &OsStr) -> Result<libloading::Library, libloading::Error> {
+    use libloading::os::unix::{Library, RTLD_LOCAL, RTLD_NOW};
+    Ok(unsafe { Library::open(Some(lib_file), RTLD_NOW | RTLD_LOCAL) }?.into())
+}
+
+#[cfg(not(unix))]
+fn load_library(lib_file: &OsStr) -> Result<libloading::Library, libloading::Error> {
+    unsafe { libloading::Library::new(lib_file) }
+}
@@ -1,4 +1,5 @@
 #[derive(thiserror::Error, Debug)]
+#[non_exhaustive]
 pub enum HotReloaderError {
     #[error("Cannot lock library: {0}")]
     CannotLockLibrary(String),
@@ -8,4 +9,6 @@ pub enum HotReloaderError {
     LibraryLoadError(#[from] libloading::Error),
     #[error("The hot reloadable library has not been loaded. Has it not been built yet?")]
     LibraryNotLoaded,
+    #[error("The new library version failed the probe: {0}")]
+    ProbeFailed(String),
 }
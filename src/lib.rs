@@ -17,19 +17,28 @@ For a demo and explanation see also [this Rust and Tell presentation](https://ww
         - [Executable](#executable)
         - [Library](#library)
         - [Running it](#running-it)
+    - [Libraries with modules](#libraries-with-modules)
+    - [Async functions](#async-functions)
+    - [C-ABI libraries](#c-abi-libraries)
+    - [Hot-reloadable statics](#hot-reloadable-statics)
+    - [Hot-reloadable methods](#hot-reloadable-methods)
+    - [Hot-reloadable trait objects](#hot-reloadable-trait-objects)
     - [lib-reload events](#lib-reload-events)
 
 - [Usage tips](#usage-tips)
     - [Know the limitations](#know-the-limitations)
         - [No signature changes](#no-signature-changes)
         - [Type changes require some care](#type-changes-require-some-care)
-        - [Hot-reloadable functions cannot be generic](#hot-reloadable-functions-cannot-be-generic)
+        - [Generic functions need an instantiation list](#generic-functions-need-an-instantiation-list)
+        - [References into the library](#references-into-the-library)
         - [Global state in reloadable code](#global-state-in-reloadable-code)
     - [Use feature flags to switch between hot-reload and static code](#use-feature-flags-to-switch-between-hot-reload-and-static-code)
     - [Disable `#[no-mangle]` in release mode](#disable-no-mangle-in-release-mode)
     - [Use serialization or generic values for changing types](#use-serialization-or-generic-values-for-changing-types)
     - [Use a hot-reload friendly app structure](#use-a-hot-reload-friendly-app-structure)
     - [Use multiple libraries](#use-multiple-libraries)
+    - [Catch panics of hot functions](#catch-panics-of-hot-functions)
+    - [Probe new builds before loading them](#probe-new-builds-before-loading-them)
     - [Adjust the file watch debounce duration](#adjust-the-file-watch-debounce-duration)
     - [Debugging](#debugging)
 
@@ -37,6 +46,7 @@ For a demo and explanation see also [this Rust and Tell presentation](https://ww
 
 - [Known issues](#known-issues)
     - [tracing crate](#tracing-crate)
+    - [Thread-local destructors](#thread-local-destructors)
 
 
 # Usage
@@ -100,7 +110,9 @@ exported by the library:
 mod hot_lib {
     // Reads public no_mangle functions from lib.rs and  generates hot-reloadable
     // wrapper functions with the same signature inside this module.
-    // Note that this path relative to the project root (or absolute)
+    // Note that this path is relative to the directory of this crate's
+    // Cargo.toml or its workspace root. It can also be absolute or start with
+    // `$CARGO_MANIFEST_DIR/`.
     hot_functions_from_file!("lib/src/lib.rs");
 
     // Because we generate functions with the exact same signatures,
@@ -168,6 +180,142 @@ parallel --line-buffer --halt now,fail=1 ::: \
 
 
 
+## Libraries with modules
+
+`hot_functions_from_file!` only looks at the top-level items of a single file. To pick up the exports of a whole library crate use `hot_functions_from_crate!` with the path to the library directory instead:
+
+```ignore
+#[hot_lib_reloader::hot_module(dylib = "lib")]
+mod hot_lib {
+    hot_functions_from_crate!("lib");
+
+    pub use lib::*;
+}
+```
+
+It follows the `mod` declarations of the library (starting at `src/lib.rs` or the `[lib] path` of its `Cargo.toml`) and generates a module of the same name for each of them, so a function `lib::systems::update` is hot-reloaded as `hot_lib::systems::update`. The generated modules re-export the items of the library module they mirror. `pub use` re-exports of the library point to the hot-reloadable wrappers, other re-exported items such as types (also those of private modules) are re-exported from the library. Modules behind `#[cfg(test)]` are skipped.
+
+Types used in the signatures of functions in submodules should be public or imported from inside the library crate, imports from other crates are not carried over.
+
+## Async functions
+
+An `async fn` cannot be exported with `#[unsafe(no_mangle)]`. Annotate it with [`hot_function`] in the library instead, which exports a function returning the future boxed:
+
+```ignore
+#[hot_lib_reloader::hot_function]
+pub async fn handle(request: Request) -> Response {
+    /* ... */
+}
+```
+
+`hot_functions_from_file!` picks it up, or declare it in the `hot_module` with `#[hot_function] pub async fn handle(request: Request) -> Response {}`. The generated `hot_lib::handle(request)` returns a future that can be awaited as usual. That future is not `Send` unless the function is annotated with `#[hot_function(send)]`, both in the library and, if declared there, in the `hot_module`. Use that to spawn the future on a multi-threaded runtime.
+
+A future that is still in flight when the library is reloaded continues to run the code of the library version that created it. That library version is unloaded only after all of its futures are dropped.
+
+## C-ABI libraries
+
+Wrappers call library functions with the ABI they are declared with. A Rust library can export `pub extern "C" fn`s (with `#[repr(C)]` types), those are picked up by `hot_functions_from_file!` like any other function. Libraries written in other languages, e.g. a C library compiled to `libgeometry.so`, can be declared with an `extern` block:
+
+```ignore
+#[hot_lib_reloader::hot_module(dylib = "geometry")]
+mod hot_geometry {
+    #[hot_functions]
+    unsafe extern "C" {
+        pub fn area(width: f64, height: f64) -> f64;
+        pub safe fn version() -> u32;
+    }
+}
+```
+
+Like foreign functions in Rust, the wrappers of functions declared in a non-Rust `extern` block are `unsafe fn`s unless they are declared as `safe fn`.
+
+## Hot-reloadable statics
+
+Statics exported by the library with `#[unsafe(no_mangle)]` can be declared with `#[hot_static]` in the `hot_module`:
+
+```ignore
+// in the library
+#[unsafe(no_mangle)]
+pub static SPEED_TABLE: [f32; 4] = [0.5, 1.0, 2.0, 4.0];
+
+// in the executable
+#[hot_lib_reloader::hot_module(dylib = "lib")]
+mod hot_lib {
+    #[hot_static]
+    pub static SPEED_TABLE: [f32; 4];
+}
+
+let speed = hot_lib::SPEED_TABLE()[2];
+```
+
+The generated accessor returns a [`HotStatic`] guard that dereferences to the value. The library version the value belongs to stays loaded until the guard is dropped, so hold on to it only as long as needed to pick up new values. `static mut` is not supported.
+
+## Hot-reloadable methods
+
+Methods of `impl` blocks cannot be exported with `#[unsafe(no_mangle)]` directly. Instead, annotate the impl block in the library with [`hot_methods`] (the library needs to depend on `hot-lib-reloader` or `hot-lib-reloader-macro` for that):
+
+```ignore
+pub struct State {
+    pub counter: usize,
+}
+
+#[hot_lib_reloader::hot_methods]
+impl State {
+    pub fn step(&mut self) {
+        self.counter += 1;
+    }
+}
+```
+
+`hot_functions_from_file!` picks up such impl blocks. Alternatively declare them inside the `hot_module` like hot functions:
+
+```ignore
+#[hot_lib_reloader::hot_module(dylib = "lib")]
+mod hot_lib {
+    pub use lib::State;
+
+    #[hot_methods]
+    impl State {
+        pub fn step(&mut self) {}
+    }
+}
+```
+
+Calls like `state.step()` will then use the newest library version, also when a method is called before any of the hot functions. The hot module installs the dispatch before `main` runs on Linux, the BSDs, macOS and Windows; on other targets only once one of its functions was called for the first time. Generic impl blocks and generic methods are not supported.
+
+## Hot-reloadable trait objects
+
+The vtable of a trait object created by the library lives in the library version that created it, so a `Box<dyn Trait>` keeps calling the old code after a reload. To re-create such objects, define the trait in a crate shared by the library and the executable with a method that returns the state of the object, and export a constructor taking that state from the library:
+
+```ignore
+// in the shared crate
+pub trait Behaviour {
+    fn update(&mut self);
+    fn state(&self) -> BehaviourState;
+}
+
+// in the library
+#[unsafe(no_mangle)]
+pub fn new_behaviour(state: BehaviourState) -> Box<dyn Behaviour> {
+    Box::new(Walking::from(state))
+}
+
+// in the executable
+#[hot_lib_reloader::hot_module(dylib = "lib")]
+mod hot_lib {
+    use shared::{Behaviour, BehaviourState};
+
+    #[hot_trait(migrate = Behaviour::state)]
+    pub fn new_behaviour(state: BehaviourState) -> hot_lib_reloader::HotDyn<dyn Behaviour> {}
+}
+
+let mut behaviour = hot_lib::new_behaviour(BehaviourState::default());
+behaviour.update();
+```
+
+The returned [`HotDyn`] dereferences to the object. Mutable access checks for a reload first: the state of the old object is passed to the constructor of the new library version and the old object is dropped. Shared access uses the current object, call [`HotDyn::refresh`] to re-create it explicitly. The old library version stays loaded as long as objects created by it are alive.
+
+
 ## lib-reload events
 
 ### LibReloadObserver
@@ -207,6 +355,30 @@ fn main() {
 }
 ```
 
+The [`BlockReload`] token returned by `wait_for_about_to_reload` can also be used to reject the pending library version with [`cancel(reason)`](BlockReload::cancel), for example while state cannot be serialized. The old version stays loaded, observers are informed via [`wait_for_reload_cancelled`](LibReloadObserver::wait_for_reload_cancelled) and the next change of the library file triggers a reload again.
+
+Events are delivered reliably to every observer. An observer that is created after some reloads have already happened can find out which version it started at with [`subscribed_version`](LibReloadObserver::subscribed_version) and will then receive the events for all following reloads.
+
+By default an observer receives both kinds of events and queues them until they are read. Note that an unread about-to-reload event holds up the reload, so observers that only care about one kind of event should say so. The subscription function can take [`SubscriptionOptions`] to filter events and to use a bounded or latest-only queue:
+
+```ignore
+#[hot_lib_reloader::hot_module(dylib = "lib")]
+mod hot_lib {
+    #[lib_change_subscription]
+    pub fn subscribe_with(options: hot_lib_reloader::SubscriptionOptions) -> hot_lib_reloader::LibReloadObserver {}
+}
+
+fn main() {
+    use hot_lib_reloader::{EventQueue, ReloadEvents, SubscriptionOptions};
+    let lib_observer = hot_lib::subscribe_with(
+        SubscriptionOptions::default()
+            .events(ReloadEvents::RELOADED)
+            .queue(EventQueue::Latest),
+    );
+    /* ... */
+}
+```
+
 How to block reload to do serialization / deserialization is shown in the [reload-events example](https://github.com/rksm/hot-lib-reloader-rs/tree/master/examples/reload-events).
 
 
@@ -226,6 +398,48 @@ mod hot_lib {
 `hot_lib::was_updated()` will return `true` the first time it is called after the library was reloaded.
 It will then return false until another reload occurred.
 
+Note that the flag is shared by all callers: if two parts of your program call `was_updated()`, only the first one will see the update.
+If multiple consumers need to know about reloads, use an [`UpdateCursor`] instead. Each cursor tracks the reloads it has seen independently:
+
+```ignore
+#[hot_lib_reloader::hot_module(dylib = "lib")]
+mod hot_lib {
+    /* ... */
+    #[lib_update_cursor]
+    pub fn update_cursor() -> hot_lib_reloader::UpdateCursor {}
+}
+
+fn main() {
+    let renderer_updates = hot_lib::update_cursor();
+    let asset_updates = hot_lib::update_cursor();
+    loop {
+        if renderer_updates.changed_since_last_check() { /* ... */ }
+        if asset_updates.changed_since_last_check() { /* ... */ }
+    }
+}
+```
+
+To show the loader status, e.g. in a debug overlay, declare a `#[lib_info]` function. It returns a [`LibInfo`] snapshot with the watched and the loaded file, the hash and version of the loaded library, when it was loaded, the error of the last failed reload and whether a reload is pending:
+
+```ignore
+#[hot_lib_reloader::hot_module(dylib = "lib")]
+mod hot_lib {
+    /* ... */
+    #[lib_info]
+    pub fn info() -> hot_lib_reloader::LibInfo {}
+}
+
+fn main() {
+    let info = hot_lib::info();
+    println!("version {} loaded from {:?}", info.version, info.loaded_path);
+    if let Some(err) = &info.last_error {
+        eprintln!("last reload failed: {err}");
+    }
+}
+```
+
+The same snapshot is available from [`LibReloader::info`] when using the `LibReloader` directly.
+
 
 
 # Usage tips
@@ -248,9 +462,55 @@ Types of structs and enums that are used in both the executable and library cann
 See [use serialization](#use-serialization-or-generic-values-for-changing-types) for a way around it.
 
 
-### Hot-reloadable functions cannot be generic
+### Generic functions need an instantiation list
 
 Since `#[unsafe(no_mangle)]` does not support generics, generic functions can't be named / found in the library.
+Instead, you can list the types a generic function should be exported for using the [`hot_function`] attribute in the library:
+
+```ignore
+#[hot_lib_reloader::hot_function(instantiate = [u32, f32, MyType])]
+pub fn double<T: std::ops::Add<Output = T> + Copy>(x: T) -> T {
+    x + x
+}
+```
+
+`hot_functions_from_file!` (or declaring the function with the same attribute inside the `hot_module`) generates a generic `double` wrapper that works with exactly those types. Calling it with any other type is a compile error.
+Only functions with a single type parameter are supported.
+
+`impl Trait` arguments and return types make a function generic as well. Use trait objects like `&dyn Trait` or `Box<dyn Trait>` for hot-reloadable functions instead.
+
+### References into the library
+
+Values that point into the code or data of the library, like a returned `&'static str`, a `Box<dyn Fn()>` or a trait object, become dangling when the library version they belong to is unloaded on reload. Using them afterwards is undefined behavior.
+
+A library version is only unloaded once nothing uses it anymore: no hot function call is running and no [`GenerationGuard`] of it is alive. [`KeepLoaded`] values (the futures of async hot functions), [`HotStatic`]s and [`HotDyn`] objects hold such a guard. To keep other values valid, store them together with a guard:
+
+```ignore
+#[hot_lib_reloader::hot_module(dylib = "lib")]
+mod hot_lib {
+    /* ... */
+    #[lib_generation_guard]
+    pub fn generation_guard() -> Option<hot_lib_reloader::GenerationGuard> {}
+}
+
+// take the guard first, the name belongs to its version if it is still the current one afterwards
+let guard = hot_lib::generation_guard().unwrap();
+let name: &'static str = hot_lib::name();
+assert!(guard.is_current());
+```
+
+[`LibInfo::resident_generations`] tells how many library versions are currently loaded.
+
+The leak mode keeps old library versions loaded instead, trading memory for safety during development. It is enabled by capping the number of retained versions and / or the size of their files:
+
+```ignore
+#[hot_lib_reloader::hot_module(dylib = "lib", leak_max_versions = 16, leak_max_bytes = 1_000_000_000)]
+mod hot_lib {
+    /* ... */
+}
+```
+
+When retaining another version would exceed a cap, the oldest retained versions are unloaded and a warning is logged. [`LibInfo::retained_versions`] tells how many versions are currently retained. When using the [`LibReloader`] directly, call [`LibReloader::set_leak_limit`].
 
 ### Global state in reloadable code
 
@@ -291,6 +551,29 @@ mod hot_lib { /*...*/ }
 
 To run the static version just use `cargo run` the hot reloadable variant with `cargo run --features reload`.
 
+### Static fallback
+
+Instead of switching between two modules you can let the `hot_module` macro do it. With `static_fallback` and `enable` the module is hot-reloadable only if the `enable` predicate holds. Otherwise the module re-exports the functions of the statically linked crate named by `static_fallback`:
+
+```ignore
+use hot_lib::*;
+
+#[hot_lib_reloader::hot_module(
+    dylib = "lib",
+    static_fallback = "lib",
+    enable = cfg(feature = "reload")
+)]
+mod hot_lib { /*...*/ }
+```
+
+The fallback module has the same API: `#[hot_static]` accessors return a [`HotStatic`] that does not keep anything loaded, `#[lib_version]` is always 0, `#[lib_updated]` is always `false`, `#[lib_init]` always succeeds, `#[lib_info]` returns a default `LibInfo`, `#[lib_generation_guard]` returns `None`, `#[hot_trait]` objects are never re-created and subscribers never get events. As the macro is used in both cases, `hot-lib-reloader` cannot be an optional dependency then. Switch between two modules like above to keep it optional.
+
+For `hot_functions_from_file!` the fallback re-exports the library module defined by the file. Its path is derived from the location of the file below the `src` directory of the library, e.g. `lib/src/systems/mod.rs` is `lib::systems`. If the library uses `#[path]` attributes or another layout, give the module path explicitly:
+
+```ignore
+hot_functions_from_file!("lib/systems.rs", module = "crate::systems");
+```
+
 
 ### Disable `#[no-mangle]` in release mode
 
@@ -306,6 +589,25 @@ Conditionally use `#[no_mangle]` in your library:
 
 To run the static version just use `cargo run` the hot reloadable variant with `cargo run --features reload`.
 
+`hot_functions_from_file!` and `hot_functions_from_crate!` evaluate `#[cfg(...)]` and `#[cfg_attr(..., unsafe(no_mangle))]` of the library source. `test`, `doc` and `doctest` are always off. The features of the library are not known to the executable, list the enabled ones:
+
+```ignore
+hot_functions_from_file!("lib/src/lib.rs", features = ["reload"]);
+```
+
+Features that are not listed are off, the macro warns about them if there is no `features = [...]` at all. Other predicates the macro cannot decide are put on the generated functions as `#[cfg(...)]` so they are evaluated with the configuration of the executable. Items behind predicates like `version(...)` are skipped with a warning. To decide target predicates already when scanning, forward the build configuration with a build script of the executable:
+
+```ignore
+// build.rs
+fn main() {
+    for (key, value) in std::env::vars() {
+        if key.starts_with("CARGO_CFG_") {
+            println!("cargo:rustc-env={key}={value}");
+        }
+    }
+}
+```
+
 #### Using `no-mangle-if-debug` macro
 
 Use the [no-mangle-if-debug attribute macro](./macro-no-mangle-if-debug). It will conditionally disable name mangling, depending on wether you build release or debug mode.
@@ -375,6 +677,43 @@ But even when using a framework that takes control, chances are that there are w
 See the [egui](https://github.com/rksm/hot-lib-reloader-rs/tree/master/examples/hot-egui) and [tokio](https://github.com/rksm/hot-lib-reloader-rs/tree/master/examples/reload-events) examples possible setupts.
 
 
+## Catch panics of hot functions
+
+A panic in a freshly edited library function unwinds into the executable and might take it down. With the `on_panic` option of `hot_module` the wrappers catch the panic and subscribers are informed about it, see [`LibReloadObserver::wait_for_panic`]. The [`HotPanic`] contains the panic message and the [generation](GenerationGuard::generation) of the library version that panicked. Policies are:
+
+- `"propagate"`: the panic continues unwinding into the caller.
+- `"try"`: like `"propagate"` but for every hot function `do_stuff` a `try_do_stuff` is generated that returns the panic as an error.
+- `"rollback"`: like `"try"` but the library version that was loaded before the panicking one is restored. It stays loaded until the library is changed again.
+
+```ignore
+#[hot_lib_reloader::hot_module(dylib = "lib", on_panic = "rollback")]
+mod hot_lib {
+    hot_functions_from_file!("lib/src/lib.rs");
+}
+
+fn main() {
+    let mut state = State::default();
+    loop {
+        if let Err(panic) = hot_lib::try_step(&mut state) {
+            eprintln!("{panic}, continuing with the previous version");
+        }
+    }
+}
+```
+
+Executable and library usually each contain their own copy of the standard library, a panic of the library can then not be caught by the executable. Export the library functions with `#[hot_lib_reloader::hot_function]` instead of `#[unsafe(no_mangle)]`, it also exports a shim that catches panics inside the library. Async functions, methods and generic functions have no such shim and no `try_` functions. In the [static fallback](#static-fallback) there are no `try_` functions for the submodules of `hot_functions_from_crate!`.
+
+## Probe new builds before loading them
+
+Loading a broken build can kill the executable right away, e.g. when an initializer of the library crashes or the library uses a symbol that cannot be resolved. With `probe = true` every new library version is first loaded by the `hot-lib-reloader-probe` helper executable in a separate process that also looks up the symbols of the hot functions. The new version is only loaded if the probe succeeds, otherwise the reload fails and the old version stays loaded. The error is reported by [`LibInfo::last_error`].
+
+```ignore
+#[hot_module(dylib = "lib", probe = true, probe_timeout_ms = 2000)]
+/* ... */
+```
+
+The probe is killed after `probe_timeout_ms`, 5000ms by default. Build it next to your executable with `cargo build -p hot-lib-reloader --bin hot-lib-reloader-probe` (with `hot-lib-reloader` being a dependency) or install it with `cargo install hot-lib-reloader`. The `HOT_LIB_RELOADER_PROBE` environment variable can point to it as well. Without the macro, see [`LibReloader::set_probe`].
+
 ## Adjust the file watch debounce duration
 
 The `hot_module` macro allows setting the `file_watch_debounce` attribute which defines the debounce duration for file changes in milliseconds.
@@ -405,6 +744,28 @@ mod hot_lib {
 }
 ```
 
+Both can also be changed when running the executable, without recompiling it, using the environment variables `HOT_LIB_DIR_<MODULE>` and `HOT_LIB_NAME_<MODULE>`, where `<MODULE>` is the name of the hot module in upper case:
+
+```shell
+HOT_LIB_DIR_HOT_LIB=/path/to/build HOT_LIB_NAME_HOT_LIB=other_lib ./bin
+```
+
+The values that are used are logged when the library is first loaded.
+
+The library is loaded when a hot function is first called, a wrong location makes that call panic. To check it up front declare an init function and call it at startup:
+
+```ignore
+#[hot_lib_reloader::hot_module(dylib = "lib")]
+mod hot_lib {
+    #[lib_init]
+    pub fn init() -> Result<(), hot_lib_reloader::HotReloaderError> {}
+}
+
+fn main() {
+    hot_lib::init().expect("failed to load the hot library");
+}
+```
+
 ### Adjust the dylib filename
 
 The `hot_module` macro allows setting the shadow file name using the `loaded_lib_name_template` parameter.
@@ -468,6 +829,23 @@ When used with the `tracing` crate multiple issues can occur:
 
 If you can, don't use `hot-lib-reloader` in combination with `tracing`.
 
+## Thread-local destructors
+
+On Linux, a library that registered a destructor of a thread-local variable (a `thread_local!` with a type that implements `Drop`, used by the library itself or by one of its dependencies) is not unloaded by `dlclose` until the thread exits. Depending on the libc, it can also crash when the thread exits after the library was unloaded. A warning is logged when a library version stays loaded after it was closed.
+
+Such a library is detected when a closed version is still mapped into the process. Later versions can be kept loaded instead of closing them:
+
+```ignore
+#[hot_lib_reloader::hot_module(dylib = "lib", tls_destructors = "never_unload")]
+mod hot_lib {
+    /* ... */
+}
+```
+
+[`LibInfo::tls_destructors`] tells if the library was detected as such a library, versions that stayed loaded count towards [`LibInfo::resident_generations`]. When using the [`LibReloader`] directly, call [`LibReloader::set_tls_destructor_policy`].
+
+`never_unload` cannot protect the first such version, the library is only detected by closing it. glibc keeps that version loaded anyway. With a libc that unloads it, the thread can still crash when it exits and the library is not detected at all. Checking for `__cxa_thread_atexit_impl` before closing does not help, every library linked against `std` imports it.
+
 
 
 # License
@@ -479,6 +857,7 @@ If you can, don't use `hot-lib-reloader` in combination with `tracing`.
 mod error;
 mod lib_reload_events;
 mod lib_reloader;
+mod probe;
 
 #[cfg(feature = "verbose")]
 mod log;
@@ -486,7 +865,17 @@ mod log;
 #[cfg(target_os = "macos")]
 mod codesign;
 
+#[cfg(target_os = "linux")]
+mod tls_destructors;
+
 pub use error::HotReloaderError;
-pub use hot_lib_reloader_macro::hot_module;
-pub use lib_reload_events::{BlockReload, ChangedEvent, LibReloadNotifier, LibReloadObserver};
-pub use lib_reloader::LibReloader;
+pub use hot_lib_reloader_macro::{hot_function, hot_methods, hot_module};
+pub use lib_reload_events::{
+    BlockReload, ChangedEvent, EventQueue, HotPanic, LibReloadNotifier, LibReloadObserver,
+    ReloadEvents, SubscriptionOptions, UpdateCursor,
+};
+pub use lib_reloader::{
+    GenerationGuard, HotDyn, HotStatic, KeepLoaded, LeakLimit, LibInfo, LibReloader,
+    TlsDestructorPolicy,
+};
+pub use probe::LibProbe;
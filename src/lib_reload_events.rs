@@ -1,6 +1,10 @@
 use std::{
-    borrow::BorrowMut,
-    sync::{Arc, Condvar, Mutex, mpsc},
+    collections::VecDeque,
+    sync::{
+        Arc, Condvar, Mutex,
+        atomic::{AtomicUsize, Ordering},
+        mpsc,
+    },
     time::Duration,
 };
 
@@ -12,6 +16,8 @@ use std::{
 pub enum ChangedEvent {
     LibAboutToReload(BlockReload),
     LibReloaded,
+    LibReloadCancelled(String),
+    HotFunctionPanicked(HotPanic),
 }
 
 impl std::fmt::Debug for ChangedEvent {
@@ -19,23 +25,207 @@ impl std::fmt::Debug for ChangedEvent {
         match self {
             Self::LibAboutToReload(_) => write!(f, "LibAboutToReload"),
             Self::LibReloaded => write!(f, "LibReloaded"),
+            Self::LibReloadCancelled(reason) => write!(f, "LibReloadCancelled({reason:?})"),
+            Self::HotFunctionPanicked(panic) => write!(f, "HotFunctionPanicked({panic:?})"),
         }
     }
 }
 
+impl ChangedEvent {
+    fn kind(&self) -> ReloadEvents {
+        match self {
+            Self::LibAboutToReload(_) => ReloadEvents::ABOUT_TO_RELOAD,
+            Self::LibReloaded => ReloadEvents::RELOADED,
+            Self::LibReloadCancelled(_) => ReloadEvents::CANCELLED,
+            Self::HotFunctionPanicked(_) => ReloadEvents::PANICKED,
+        }
+    }
+
+    /// Can a full [`EventQueue::Bounded`] drop the event? Reloads and their
+    /// cancellations are always delivered.
+    fn droppable(&self) -> bool {
+        matches!(
+            self,
+            Self::LibAboutToReload(_) | Self::HotFunctionPanicked(_)
+        )
+    }
+}
+
+/// A panic of a library function called through a hot function wrapper, see
+/// the `on_panic` option of the [`hot_module`](crate::hot_module) attribute.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HotPanic {
+    /// Name of the library function that panicked.
+    pub function: String,
+    /// The panic message, if the panic payload was a string.
+    pub message: Option<String>,
+    /// The generation of the library version that panicked, see
+    /// [`GenerationGuard::generation`](crate::GenerationGuard::generation).
+    pub version: usize,
+}
+
+impl HotPanic {
+    /// Needs to be public as it is used in the `hot_module` macro.
+    #[doc(hidden)]
+    pub fn new(function: &str, payload: &(dyn std::any::Any + Send), version: usize) -> Self {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned());
+        Self {
+            function: function.to_string(),
+            message,
+            version,
+        }
+    }
+}
+
+impl std::fmt::Display for HotPanic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "hot function {} of library version {} panicked",
+            self.function, self.version
+        )?;
+        if let Some(message) = &self.message {
+            write!(f, ": {message}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for HotPanic {}
+
+/// A set of event kinds a [`LibReloadObserver`] is interested in. Events not
+/// contained in the set are never queued for that observer.
+///
+/// ```
+/// use hot_lib_reloader::ReloadEvents;
+/// let events = ReloadEvents::ABOUT_TO_RELOAD | ReloadEvents::RELOADED;
+/// assert!(events.contains(ReloadEvents::RELOADED));
+/// assert!(!events.contains(ReloadEvents::CANCELLED));
+/// assert!(ReloadEvents::ALL.contains(events));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ReloadEvents(u8);
+
+impl ReloadEvents {
+    /// See [`LibReloadObserver::wait_for_about_to_reload`].
+    pub const ABOUT_TO_RELOAD: Self = Self(1);
+    /// See [`LibReloadObserver::wait_for_reload`].
+    pub const RELOADED: Self = Self(1 << 1);
+    /// See [`LibReloadObserver::wait_for_reload_cancelled`].
+    pub const CANCELLED: Self = Self(1 << 2);
+    /// See [`LibReloadObserver::wait_for_panic`].
+    pub const PANICKED: Self = Self(1 << 3);
+    /// All event kinds.
+    pub const ALL: Self =
+        Self(Self::ABOUT_TO_RELOAD.0 | Self::RELOADED.0 | Self::CANCELLED.0 | Self::PANICKED.0);
+
+    /// Are all events of `other` part of this set?
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl Default for ReloadEvents {
+    fn default() -> Self {
+        Self::ALL
+    }
+}
+
+impl std::ops::BitOr for ReloadEvents {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+/// How events are queued for a [`LibReloadObserver`] until it reads them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EventQueue {
+    /// Queue every event. No event is ever dropped.
+    #[default]
+    Unbounded,
+    /// Queue up to n events (at least 1). When the queue is full, the oldest
+    /// about-to-reload or panic event is dropped to make room. Reloaded and
+    /// cancelled events are never dropped, they are queued even if that
+    /// exceeds n.
+    Bounded(usize),
+    /// Only keep the most recent event, replacing an unread older one.
+    Latest,
+}
+
+/// Options for [`LibReloadNotifier::subscribe_with`]. The defaults (all events,
+/// unbounded queue) match [`LibReloadNotifier::subscribe`].
+///
+/// Note that a queued [`ChangedEvent::LibAboutToReload`] event holds a
+/// [`BlockReload`] token. An observer that receives about-to-reload events but
+/// does not read them will hold up reloading. If you only care about finished
+/// reloads, subscribe to [`ReloadEvents::RELOADED`] only.
+///
+/// ```
+/// use hot_lib_reloader::{EventQueue, ReloadEvents, SubscriptionOptions};
+/// let options = SubscriptionOptions::default()
+///     .events(ReloadEvents::RELOADED)
+///     .queue(EventQueue::Latest);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SubscriptionOptions {
+    events: ReloadEvents,
+    queue: EventQueue,
+}
+
+impl SubscriptionOptions {
+    /// Only receive the given event kinds.
+    pub fn events(mut self, events: ReloadEvents) -> Self {
+        self.events = events;
+        self
+    }
+
+    /// Queue events according to `queue`.
+    pub fn queue(mut self, queue: EventQueue) -> Self {
+        self.queue = queue;
+        self
+    }
+}
+
 /// See [`LibReloadObserver::wait_for_about_to_reload`].
 ///
 /// [`BlockReload`] is implemented using a simple counting scheme to track how
 /// many tokens are floating around. If the number reaches 0 the update can
 /// continue.
+///
+/// Instead of just delaying the reload, a token can also reject the pending
+/// library version altogether with [`BlockReload::cancel`].
 #[derive(Debug)]
 pub struct BlockReload {
-    pub(crate) pending: Arc<(Mutex<usize>, Condvar)>,
+    pub(crate) pending: Arc<PendingReload>,
+}
+
+impl BlockReload {
+    /// Skip loading the pending library version. Subscribers get informed via
+    /// [`LibReloadObserver::wait_for_reload_cancelled`]. The currently loaded
+    /// version stays in place and the next change of the library file will
+    /// trigger a reload again.
+    ///
+    /// If multiple tokens are cancelled, the first reason wins.
+    pub fn cancel(self, reason: impl Into<String>) {
+        let reason = reason.into();
+        log::debug!("reload cancelled: {reason}");
+        self.pending
+            .state
+            .lock()
+            .unwrap()
+            .cancelled
+            .get_or_insert(reason);
+    }
 }
 
 impl Clone for BlockReload {
     fn clone(&self) -> Self {
-        **(self.pending.0.lock().unwrap().borrow_mut()) += 1;
+        self.pending.state.lock().unwrap().count += 1;
         Self {
             pending: self.pending.clone(),
         }
@@ -44,12 +234,24 @@ impl Clone for BlockReload {
 
 impl Drop for BlockReload {
     fn drop(&mut self) {
-        let (counter, cond) = &*self.pending;
-        *counter.lock().unwrap() -= 1;
-        cond.notify_one();
+        self.pending.state.lock().unwrap().count -= 1;
+        self.pending.cond.notify_one();
     }
 }
 
+/// Shared state of all [`BlockReload`] tokens of one about-to-reload event.
+#[derive(Debug)]
+pub(crate) struct PendingReload {
+    state: Mutex<PendingReloadState>,
+    cond: Condvar,
+}
+
+#[derive(Debug)]
+struct PendingReloadState {
+    count: usize,
+    cancelled: Option<String>,
+}
+
 /// A [`LibReloadObserver`] allows to wait for library changes. See
 /// - [`LibReloadObserver::wait_for_about_to_reload`] and
 /// - [`LibReloadObserver::wait_for_reload`]
@@ -86,18 +288,33 @@ impl Drop for BlockReload {
 pub struct LibReloadObserver {
     // needs to be public b/c it is used inside the [`hot_module`] macro.
     #[doc(hidden)]
+    #[deprecated(
+        note = "use the `wait_for_*` methods, only observers with an `EventQueue::Unbounded` receive events here"
+    )]
     pub rx: mpsc::Receiver<ChangedEvent>,
+    queue: EventReceiver,
+    events: ReloadEvents,
+    version: usize,
 }
 
 impl LibReloadObserver {
+    /// The library version that was loaded when this observer was created. The
+    /// observer is guaranteed to receive the events of every reload that
+    /// happens after that version, so there is no gap between reading the
+    /// version and waiting for events.
+    pub fn subscribed_version(&self) -> usize {
+        self.version
+    }
+
     /// A call to this method will do a blocking wait until the watched library is
     /// about to change. It returns a [`BlockReload`] token. While this token is in
     /// scope you will prevent the pending update to proceed. This is useful for
     /// doing preparations for the update and while the old library version is still
     /// loaded. You can for example serialize state.
     pub fn wait_for_about_to_reload(&self) -> BlockReload {
+        self.assert_subscribed_to(ReloadEvents::ABOUT_TO_RELOAD);
         loop {
-            match self.rx.recv() {
+            match self.recv() {
                 Ok(ChangedEvent::LibAboutToReload(block)) => return block,
                 Err(err) => {
                     panic!("LibReloadObserver failed to wait for event from reloader: {err}")
@@ -109,8 +326,9 @@ impl LibReloadObserver {
 
     /// Like [`Self::wait_for_about_to_reload`] but for a limited time. In case of a timeout return `None`.
     pub fn wait_for_about_to_reload_timeout(&self, timeout: Duration) -> Option<BlockReload> {
+        self.assert_subscribed_to(ReloadEvents::ABOUT_TO_RELOAD);
         loop {
-            match self.rx.recv_timeout(timeout) {
+            match self.recv_timeout(timeout) {
                 Ok(ChangedEvent::LibAboutToReload(block)) => return Some(block),
                 Err(_) => return None,
                 _ => continue,
@@ -120,8 +338,9 @@ impl LibReloadObserver {
 
     /// Will do blocking wait until a new library version is loaded.
     pub fn wait_for_reload(&self) {
+        self.assert_subscribed_to(ReloadEvents::RELOADED);
         loop {
-            match self.rx.recv() {
+            match self.recv() {
                 Ok(ChangedEvent::LibReloaded) => return,
                 Err(err) => {
                     panic!("LibReloadObserver failed to wait for event from reloader: {err}")
@@ -133,21 +352,311 @@ impl LibReloadObserver {
 
     /// Like [`Self::wait_for_reload`] but for a limited time. In case of a timeout return `false`.
     pub fn wait_for_reload_timeout(&self, timeout: Duration) -> bool {
+        self.assert_subscribed_to(ReloadEvents::RELOADED);
         loop {
-            match self.rx.recv_timeout(timeout) {
+            match self.recv_timeout(timeout) {
                 Ok(ChangedEvent::LibReloaded) => return true,
                 Err(_) => return false,
                 _ => continue,
             }
         }
     }
+
+    /// Will do a blocking wait until a pending reload was cancelled using
+    /// [`BlockReload::cancel`] and returns the reason for the cancellation.
+    ///
+    /// Note that [`Self::wait_for_reload`] keeps waiting for the next reload
+    /// that actually happens when a reload is cancelled.
+    pub fn wait_for_reload_cancelled(&self) -> String {
+        self.assert_subscribed_to(ReloadEvents::CANCELLED);
+        loop {
+            match self.recv() {
+                Ok(ChangedEvent::LibReloadCancelled(reason)) => return reason,
+                Err(err) => {
+                    panic!("LibReloadObserver failed to wait for event from reloader: {err}")
+                }
+                _ => continue,
+            }
+        }
+    }
+
+    /// Like [`Self::wait_for_reload_cancelled`] but for a limited time. In case of a timeout return `None`.
+    pub fn wait_for_reload_cancelled_timeout(&self, timeout: Duration) -> Option<String> {
+        self.assert_subscribed_to(ReloadEvents::CANCELLED);
+        loop {
+            match self.recv_timeout(timeout) {
+                Ok(ChangedEvent::LibReloadCancelled(reason)) => return Some(reason),
+                Err(_) => return None,
+                _ => continue,
+            }
+        }
+    }
+
+    /// Will do a blocking wait until a library function called through a hot
+    /// function wrapper panicked. Only happens if the `hot_module` has an
+    /// `on_panic` policy.
+    pub fn wait_for_panic(&self) -> HotPanic {
+        self.assert_subscribed_to(ReloadEvents::PANICKED);
+        loop {
+            match self.recv() {
+                Ok(ChangedEvent::HotFunctionPanicked(panic)) => return panic,
+                Err(err) => {
+                    panic!("LibReloadObserver failed to wait for event from reloader: {err}")
+                }
+                _ => continue,
+            }
+        }
+    }
+
+    /// Like [`Self::wait_for_panic`] but for a limited time. In case of a timeout return `None`.
+    pub fn wait_for_panic_timeout(&self, timeout: Duration) -> Option<HotPanic> {
+        self.assert_subscribed_to(ReloadEvents::PANICKED);
+        loop {
+            match self.recv_timeout(timeout) {
+                Ok(ChangedEvent::HotFunctionPanicked(panic)) => return Some(panic),
+                Err(_) => return None,
+                _ => continue,
+            }
+        }
+    }
+
+    #[allow(deprecated)]
+    fn recv(&self) -> Result<ChangedEvent, RecvError> {
+        match &self.queue {
+            EventReceiver::Channel => self.rx.recv().map_err(|_| RecvError::Disconnected),
+            EventReceiver::Slot(slot) => slot.recv(None),
+        }
+    }
+
+    #[allow(deprecated)]
+    fn recv_timeout(&self, timeout: Duration) -> Result<ChangedEvent, RecvError> {
+        match &self.queue {
+            EventReceiver::Channel => self.rx.recv_timeout(timeout).map_err(|err| match err {
+                mpsc::RecvTimeoutError::Timeout => RecvError::Timeout,
+                mpsc::RecvTimeoutError::Disconnected => RecvError::Disconnected,
+            }),
+            EventReceiver::Slot(slot) => slot.recv(Some(timeout)),
+        }
+    }
+
+    /// Waiting for an event kind that was filtered out would block forever.
+    fn assert_subscribed_to(&self, events: ReloadEvents) {
+        assert!(
+            self.events.contains(events),
+            "LibReloadObserver is not subscribed to {events:?} events"
+        );
+    }
+}
+
+/// Sending half of an observer's event queue.
+enum EventSender {
+    Unbounded(mpsc::Sender<ChangedEvent>),
+    Slot(SlotSender),
+}
+
+impl EventSender {
+    /// Returns `false` if the observer is gone.
+    fn send(&self, evt: ChangedEvent) -> bool {
+        match self {
+            Self::Unbounded(tx) => tx.send(evt).is_ok(),
+            Self::Slot(tx) => tx.send(evt),
+        }
+    }
+}
+
+/// Receiving half of an observer's event queue.
+enum EventReceiver {
+    /// The events arrive at [`LibReloadObserver::rx`].
+    Channel,
+    Slot(Arc<EventSlot>),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum RecvError {
+    Timeout,
+    Disconnected,
+}
+
+impl std::fmt::Display for RecvError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Timeout => write!(f, "timed out waiting on event queue"),
+            Self::Disconnected => write!(f, "event queue is disconnected"),
+        }
+    }
+}
+
+/// The sender and receiver of a queue. The channel is the receiver of
+/// [`EventQueue::Unbounded`], it is disconnected for the other queues.
+fn event_queue(queue: EventQueue) -> (EventSender, EventReceiver, mpsc::Receiver<ChangedEvent>) {
+    let (tx, rx) = mpsc::channel();
+    match queue {
+        EventQueue::Unbounded => (EventSender::Unbounded(tx), EventReceiver::Channel, rx),
+        EventQueue::Bounded(_) | EventQueue::Latest => {
+            let slot = Arc::new(EventSlot::default());
+            let tx = SlotSender {
+                slot: Arc::downgrade(&slot),
+                queue,
+            };
+            (EventSender::Slot(tx), EventReceiver::Slot(slot), rx)
+        }
+    }
+}
+
+/// Holds the unread events for [`EventQueue::Bounded`] and
+/// [`EventQueue::Latest`].
+#[derive(Default)]
+struct EventSlot {
+    state: Mutex<SlotState>,
+    cond: Condvar,
+}
+
+#[derive(Default)]
+struct SlotState {
+    events: VecDeque<ChangedEvent>,
+    closed: bool,
+}
+
+impl EventSlot {
+    fn recv(&self, timeout: Option<Duration>) -> Result<ChangedEvent, RecvError> {
+        let deadline = timeout.map(|timeout| std::time::Instant::now() + timeout);
+        let mut state = self.state.lock().unwrap();
+        loop {
+            if let Some(evt) = state.events.pop_front() {
+                return Ok(evt);
+            }
+            if state.closed {
+                return Err(RecvError::Disconnected);
+            }
+            state = match deadline {
+                None => self.cond.wait(state).unwrap(),
+                Some(deadline) => {
+                    let now = std::time::Instant::now();
+                    if now >= deadline {
+                        return Err(RecvError::Timeout);
+                    }
+                    self.cond.wait_timeout(state, deadline - now).unwrap().0
+                }
+            };
+        }
+    }
+}
+
+/// Only holds a weak reference so that a dropped observer can be detected.
+struct SlotSender {
+    slot: std::sync::Weak<EventSlot>,
+    queue: EventQueue,
+}
+
+impl SlotSender {
+    fn send(&self, evt: ChangedEvent) -> bool {
+        let Some(slot) = self.slot.upgrade() else {
+            return false;
+        };
+        let mut state = slot.state.lock().unwrap();
+        let events = &mut state.events;
+        match self.queue {
+            EventQueue::Bounded(n) if events.len() >= n.max(1) => {
+                match events.iter().position(ChangedEvent::droppable) {
+                    Some(oldest) => {
+                        let dropped = events.remove(oldest);
+                        log::debug!("event queue of subscriber is full, dropping {dropped:?}");
+                    }
+                    None if evt.droppable() => {
+                        log::debug!("event queue of subscriber is full, dropping {evt:?}");
+                        return true;
+                    }
+                    None => {}
+                }
+            }
+            EventQueue::Latest => events.clear(),
+            _ => {}
+        }
+        events.push_back(evt);
+        drop(state);
+        slot.cond.notify_one();
+        true
+    }
+}
+
+impl Drop for SlotSender {
+    fn drop(&mut self) {
+        if let Some(slot) = self.slot.upgrade() {
+            slot.state.lock().unwrap().closed = true;
+            slot.cond.notify_one();
+        }
+    }
+}
+
+/// Tracks library reloads for one consumer. Created by a function annotated
+/// with `#[lib_update_cursor]` in a [`hot_module`](crate::hot_module):
+///
+/// ```ignore
+/// #[hot_module(dylib = "lib")]
+/// mod hot_lib {
+///     #[lib_update_cursor]
+///     pub fn update_cursor() -> hot_lib_reloader::UpdateCursor {}
+/// }
+///
+/// let renderer_cursor = hot_lib::update_cursor();
+/// let assets_cursor = hot_lib::update_cursor();
+///
+/// // after a reload, both cursors report the change independently
+/// if renderer_cursor.changed_since_last_check() { /* ... */ }
+/// if assets_cursor.changed_since_last_check() { /* ... */ }
+/// ```
+///
+/// In contrast to the `#[lib_updated]` flag that is shared by all callers,
+/// every cursor observes every reload.
+pub struct UpdateCursor {
+    notifier: Arc<LibReloadNotifier>,
+    seen_version: AtomicUsize,
+}
+
+impl UpdateCursor {
+    /// Returns `true` if the library was reloaded since this method was last
+    /// called on this cursor (or since the cursor was created).
+    pub fn changed_since_last_check(&self) -> bool {
+        let version = self.notifier.version();
+        self.seen_version.swap(version, Ordering::AcqRel) != version
+    }
+}
+
+impl Clone for UpdateCursor {
+    fn clone(&self) -> Self {
+        Self {
+            notifier: self.notifier.clone(),
+            seen_version: AtomicUsize::new(self.seen_version.load(Ordering::Acquire)),
+        }
+    }
+}
+
+impl std::fmt::Debug for UpdateCursor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("UpdateCursor")
+            .field("seen_version", &self.seen_version)
+            .finish()
+    }
 }
 
 /// Needs to be public as it is used in the `hot_module` macro.
 #[derive(Default)]
 #[doc(hidden)]
 pub struct LibReloadNotifier {
-    subscribers: Arc<Mutex<Vec<mpsc::Sender<ChangedEvent>>>>,
+    state: Arc<Mutex<NotifierState>>,
+}
+
+/// Subscribers and the version counter share one lock so that subscribing and
+/// reading the version is atomic with respect to sending reload events.
+#[derive(Default)]
+struct NotifierState {
+    subscribers: Vec<Subscriber>,
+    version: usize,
+}
+
+struct Subscriber {
+    events: ReloadEvents,
+    tx: EventSender,
 }
 
 impl LibReloadNotifier {
@@ -156,60 +665,126 @@ impl LibReloadNotifier {
     /// The count used here represents [`BlockReload`] tokens that are still
     /// floating around. When a token is dropped the count is decremented and
     /// the condvar signaled.
+    ///
+    /// Returns the reason if one of the tokens was used to
+    /// [cancel](BlockReload::cancel) the reload.
     #[doc(hidden)]
-    pub fn send_about_to_reload_event_and_wait_for_blocks(&self) {
-        let pending = Arc::new((Mutex::new(1), std::sync::Condvar::new()));
+    pub fn send_about_to_reload_event_and_wait_for_blocks(&self) -> Option<String> {
+        let pending = Arc::new(PendingReload {
+            state: Mutex::new(PendingReloadState {
+                count: 1,
+                cancelled: None,
+            }),
+            cond: Condvar::new(),
+        });
         let block = BlockReload {
             pending: pending.clone(),
         };
         self.notify(ChangedEvent::LibAboutToReload(block));
-        let (counter, cond) = &*pending;
         log::trace!(
             "about-to-change library event, waiting for {}",
-            counter.lock().unwrap()
+            pending.state.lock().unwrap().count
         );
-        let _guard = cond
-            .wait_while(counter.lock().unwrap(), |pending| {
+        let mut state = pending
+            .cond
+            .wait_while(pending.state.lock().unwrap(), |state| {
                 log::trace!(
                     "about-to-change library event, now waiting for {}",
-                    *pending
+                    state.count
                 );
-                *pending > 0
+                state.count > 0
             })
             .unwrap();
+        state.cancelled.take()
+    }
+
+    /// Informs subscribers that the pending reload was cancelled.
+    #[doc(hidden)]
+    pub fn send_reload_cancelled_event(&self, reason: String) {
+        self.notify(ChangedEvent::LibReloadCancelled(reason));
+    }
+
+    /// Informs subscribers that a hot function panicked.
+    #[doc(hidden)]
+    pub fn send_panic_event(&self, panic: HotPanic) {
+        self.notify(ChangedEvent::HotFunctionPanicked(panic));
     }
 
+    /// Bumps the version and informs subscribers about it.
     #[doc(hidden)]
     pub fn send_reloaded_event(&self) {
-        self.notify(ChangedEvent::LibReloaded);
+        let mut state = self.state.lock().unwrap();
+        state.version += 1;
+        Self::notify_locked(&mut state, ChangedEvent::LibReloaded);
+    }
+
+    /// Needs to be public as it is used in the `hot_module` macro.
+    ///
+    /// Create an [`UpdateCursor`] starting at the current version.
+    #[doc(hidden)]
+    pub fn update_cursor(self: &Arc<Self>) -> UpdateCursor {
+        UpdateCursor {
+            notifier: self.clone(),
+            seen_version: AtomicUsize::new(self.version()),
+        }
+    }
+
+    /// The number of reloads that have been announced via
+    /// [`Self::send_reloaded_event`].
+    #[doc(hidden)]
+    pub fn version(&self) -> usize {
+        self.state.lock().unwrap().version
     }
 
     fn notify(&self, evt: ChangedEvent) {
-        if let Ok(mut subscribers) = self.subscribers.try_lock() {
-            let n = subscribers.len();
-            log::trace!("sending {evt:?} to {n} subscribers");
-            // keep only those subscribers that are still around and kicking.
-            subscribers.retain(|tx| tx.send(evt.clone()).is_ok());
-            let removed = n - subscribers.len();
-            if removed > 0 {
-                log::debug!(
-                    "removing {removed} subscriber{}",
-                    if removed == 1 { "" } else { "s" }
-                );
-            }
+        let mut state = self.state.lock().unwrap();
+        Self::notify_locked(&mut state, evt);
+    }
+
+    fn notify_locked(state: &mut NotifierState, evt: ChangedEvent) {
+        let subscribers = &mut state.subscribers;
+        let n = subscribers.len();
+        log::trace!("sending {evt:?} to {n} subscribers");
+        // keep only those subscribers that are still around and kicking.
+        // Subscribers not interested in this kind of event never see it.
+        let kind = evt.kind();
+        subscribers.retain(|sub| !sub.events.contains(kind) || sub.tx.send(evt.clone()));
+        let removed = n - subscribers.len();
+        if removed > 0 {
+            log::debug!(
+                "removing {removed} subscriber{}",
+                if removed == 1 { "" } else { "s" }
+            );
         }
     }
 
     /// Needs to be public as it is used in the `hot_module` macro.
     ///
     /// Create a [ChangedEvent] receiver that gets signalled when the library
-    /// changes.
+    /// changes. The observer remembers the version that was current when it was
+    /// created, see [`LibReloadObserver::subscribed_version`].
     #[doc(hidden)]
-    pub fn subscribe(&mut self) -> LibReloadObserver {
-        log::trace!("subscribe to lib change");
-        let (tx, rx) = mpsc::channel();
-        let mut subscribers = self.subscribers.lock().unwrap();
-        subscribers.push(tx);
-        LibReloadObserver { rx }
+    pub fn subscribe(&self) -> LibReloadObserver {
+        self.subscribe_with(SubscriptionOptions::default())
+    }
+
+    /// Needs to be public as it is used in the `hot_module` macro.
+    ///
+    /// Like [`Self::subscribe`] but only receives the events selected by
+    /// `options` and queues them as specified.
+    #[doc(hidden)]
+    pub fn subscribe_with(&self, options: SubscriptionOptions) -> LibReloadObserver {
+        log::trace!("subscribe to lib change with {options:?}");
+        let SubscriptionOptions { events, queue } = options;
+        let (tx, queue, rx) = event_queue(queue);
+        let mut state = self.state.lock().unwrap();
+        state.subscribers.push(Subscriber { events, tx });
+        #[allow(deprecated)]
+        LibReloadObserver {
+            rx,
+            queue,
+            events,
+            version: state.version,
+        }
     }
 }
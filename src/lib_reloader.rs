@@ -1,17 +1,22 @@
 use libloading::{Library, Symbol};
 use notify::{RecursiveMode, Watcher};
 use notify_debouncer_full::new_debouncer;
+use std::collections::VecDeque;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::{
-    Arc, Mutex,
-    atomic::{AtomicBool, AtomicU32, Ordering},
+    Arc, Mutex, Weak,
+    atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering},
     mpsc,
 };
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, SystemTime};
 
 use crate::error::HotReloaderError;
+use crate::lib_reload_events::UpdateCursor;
+use crate::probe::LibProbe;
+#[cfg(target_os = "linux")]
+use crate::tls_destructors::is_mapped;
 
 /// Manages watches a library (dylib) file, loads it using
 /// [`libloading::Library`] and [provides access to its
@@ -30,7 +35,7 @@ pub struct LibReloader {
     lib_dir: PathBuf,
     lib_name: String,
     changed: Arc<AtomicBool>,
-    lib: Option<Library>,
+    lib: Option<Arc<Library>>,
     watched_lib_file: PathBuf,
     loaded_lib_file: PathBuf,
     lib_file_hash: Arc<AtomicU32>,
@@ -38,6 +43,109 @@ pub struct LibReloader {
     #[cfg(target_os = "macos")]
     codesigner: crate::codesign::CodeSigner,
     loaded_lib_name_template: Option<String>,
+    loaded_at: Option<SystemTime>,
+    last_error: Option<String>,
+    leak_limit: Option<LeakLimit>,
+    /// Old library versions kept loaded in leak mode, oldest first.
+    retained: VecDeque<RetainedLibrary>,
+    /// The generation (load counter) of the current library, shared with the
+    /// [`GenerationGuard`]s.
+    generation: Arc<AtomicUsize>,
+    /// Replaced library versions that might still be kept loaded by guards.
+    old_generations: Vec<Weak<Library>>,
+    tls_destructor_policy: TlsDestructorPolicy,
+    /// Did a replaced version stay loaded after closing it, i.e. does the
+    /// library register destructors of thread-local variables?
+    tls_destructors: bool,
+    /// Closed versions that stayed loaded.
+    closed_but_mapped: Vec<PathBuf>,
+    /// Keep the replaced version loaded for [`LibReloader::rollback`]?
+    rollback_enabled: bool,
+    /// The version replaced by the last reload.
+    previous: Option<PreviousLibrary>,
+    probe: Option<LibProbe>,
+}
+
+/// What to do with library versions of a library that registers destructors
+/// of thread-local variables, see [`LibReloader::set_tls_destructor_policy`].
+///
+/// On Linux, `dlclose` does not unload a library once it registered such a
+/// destructor (through `__cxa_thread_atexit_impl`) or, depending on the
+/// platform and libc, crashes when the thread exits later. A library does so
+/// if it (or a crate it depends on) uses thread-local variables with a `Drop`
+/// implementation. This is detected when a closed version is still mapped.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TlsDestructorPolicy {
+    /// Unload them like all other versions. A warning is logged if a version
+    /// stays loaded after closing it.
+    #[default]
+    Unload,
+    /// Once a closed version stayed loaded, never close later versions, they
+    /// stay loaded until the process exits.
+    ///
+    /// The library is only detected by closing a version, so the first
+    /// version that registered such a destructor is always closed. With glibc
+    /// that version stays loaded anyway. With a libc that does unload it, this
+    /// policy cannot prevent a crash when the thread exits and the library is
+    /// not detected at all. There is no reliable way to tell before closing:
+    /// every library linked against `std` imports `__cxa_thread_atexit_impl`.
+    NeverUnload,
+}
+
+/// The cap of the leak mode, see [`LibReloader::set_leak_limit`]. When
+/// retaining another old library version would exceed one of the limits, the
+/// oldest retained versions are unloaded.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LeakLimit {
+    /// How many old versions to keep loaded at most.
+    pub max_versions: Option<usize>,
+    /// How many bytes the files of the old versions may add up to at most.
+    pub max_bytes: Option<u64>,
+}
+
+struct RetainedLibrary {
+    lib: Arc<Library>,
+    file: PathBuf,
+    size: u64,
+}
+
+/// A replaced library version that can be restored with
+/// [`LibReloader::rollback`].
+struct PreviousLibrary {
+    lib: Arc<Library>,
+    file: PathBuf,
+    generation: usize,
+    loaded_at: Option<SystemTime>,
+}
+
+/// A snapshot of the state of a [`LibReloader`], see [`LibReloader::info`].
+#[derive(Debug, Clone, Default)]
+pub struct LibInfo {
+    /// The library file that is watched for changes.
+    pub watched_path: PathBuf,
+    /// The copy of the library that is actually loaded, `None` if no library
+    /// is loaded.
+    pub loaded_path: Option<PathBuf>,
+    /// Hash of the content of the loaded library file.
+    pub hash: u32,
+    /// How often the library was reloaded.
+    pub version: usize,
+    /// When the loaded library was loaded.
+    pub loaded_at: Option<SystemTime>,
+    /// The error of the last failed reload, cleared by a successful one.
+    pub last_error: Option<String>,
+    /// The library file changed and the change was not loaded yet.
+    pub update_pending: bool,
+    /// Old library versions kept loaded in leak mode.
+    pub retained_versions: usize,
+    /// Library versions that are loaded: the current one plus old ones that
+    /// are kept loaded by [`GenerationGuard`]s, the leak mode or because they
+    /// stayed loaded after closing them.
+    pub resident_generations: usize,
+    /// A replaced version stayed loaded after closing it, i.e. the library
+    /// registers destructors of thread-local variables, see
+    /// [`TlsDestructorPolicy`]. Only detected on Linux.
+    pub tls_destructors: bool,
 }
 
 impl LibReloader {
@@ -79,12 +187,13 @@ impl LibReloader {
             let hash = hash_file(&loaded_lib_file);
             #[cfg(target_os = "macos")]
             codesigner.codesign(&loaded_lib_file);
-            (hash, Some(load_library(&loaded_lib_file)?))
+            (hash, Some(Arc::new(load_library(&loaded_lib_file)?)))
         } else {
             log::debug!("library {watched_lib_file:?} does not yet exist");
             (0, None)
         };
 
+        let loaded_at = lib.as_ref().map(|_| SystemTime::now());
         let lib_file_hash = Arc::new(AtomicU32::new(lib_file_hash));
         let changed = Arc::new(AtomicBool::new(false));
         let file_change_subscribers = Arc::new(Mutex::new(Vec::new()));
@@ -109,6 +218,18 @@ impl LibReloader {
             #[cfg(target_os = "macos")]
             codesigner,
             loaded_lib_name_template,
+            loaded_at,
+            last_error: None,
+            leak_limit: None,
+            retained: VecDeque::new(),
+            generation: Arc::new(AtomicUsize::new(load_counter)),
+            old_generations: Vec::new(),
+            tls_destructor_policy: TlsDestructorPolicy::default(),
+            tls_destructors: false,
+            closed_but_mapped: Vec::new(),
+            rollback_enabled: false,
+            previous: None,
+            probe: None,
         };
 
         Ok(lib_loader)
@@ -132,50 +253,210 @@ impl LibReloader {
         }
         self.changed.store(false, Ordering::Release);
 
-        self.reload()?;
+        if let Err(err) = self.reload() {
+            self.last_error = Some(err.to_string());
+            return Err(err);
+        }
+        self.last_error = None;
+
+        Ok(true)
+    }
+
+    /// A snapshot of the state of the reloader and the loaded library.
+    pub fn info(&self) -> LibInfo {
+        LibInfo {
+            watched_path: self.watched_lib_file.clone(),
+            loaded_path: self.lib.as_ref().map(|_| self.loaded_lib_file.clone()),
+            hash: self.lib_file_hash.load(Ordering::Acquire),
+            version: self.load_counter,
+            loaded_at: self.loaded_at,
+            last_error: self.last_error.clone(),
+            update_pending: self.changed.load(Ordering::Acquire),
+            retained_versions: self.retained.len(),
+            resident_generations: self.resident_generations(),
+            tls_destructors: self.tls_destructors,
+        }
+    }
+
+    /// Sets what to do with versions of a library that registers destructors
+    /// of thread-local variables when they are replaced by a new version.
+    pub fn set_tls_destructor_policy(&mut self, policy: TlsDestructorPolicy) {
+        self.tls_destructor_policy = policy;
+    }
+
+    /// A guard that keeps the currently loaded library version mapped until
+    /// it is dropped, `None` if no library is loaded.
+    pub fn generation_guard(&self) -> Option<GenerationGuard> {
+        self.lib.as_ref().map(|lib| GenerationGuard {
+            _lib: lib.clone(),
+            generation: self.generation(),
+            current: self.generation.clone(),
+        })
+    }
+
+    /// The generation of the loaded library version, see
+    /// [`GenerationGuard::generation`].
+    pub fn generation(&self) -> usize {
+        self.generation.load(Ordering::Acquire)
+    }
+
+    /// How many library versions are loaded: the current one plus old ones
+    /// that are still kept loaded by [`GenerationGuard`]s (including the ones
+    /// of [`KeepLoaded`] values and [`HotStatic`]s), the leak mode or because
+    /// they stayed loaded after closing them, see [`TlsDestructorPolicy`].
+    pub fn resident_generations(&self) -> usize {
+        self.lib.iter().count()
+            + self
+                .old_generations
+                .iter()
+                .filter(|lib| lib.strong_count() > 0)
+                .count()
+            + self
+                .closed_but_mapped
+                .iter()
+                .filter(|file| is_mapped(file))
+                .count()
+    }
+
+    /// Enables the leak mode: old library versions are kept loaded instead of
+    /// being unloaded on reload, so that references into their code and data,
+    /// like a `&'static str` or a `Box<dyn Fn()>` returned by a hot function,
+    /// stay valid. `limit` caps how much is retained, a warning is logged when
+    /// it forces an old version to be unloaded. `None` disables the leak mode,
+    /// versions retained so far are unloaded with the next reload.
+    pub fn set_leak_limit(&mut self, limit: Option<LeakLimit>) {
+        self.leak_limit = limit;
+    }
+
+    /// Keeps the library version replaced by a reload loaded so that
+    /// [`LibReloader::rollback`] can return to it. Disabling it unloads the
+    /// kept version with the next reload.
+    pub fn set_rollback_enabled(&mut self, enabled: bool) {
+        self.rollback_enabled = enabled;
+    }
+
+    /// Runs `probe` with every new library version before it is loaded. If the
+    /// probe fails the reload fails, too, and the loaded version stays in
+    /// place until the library file changes again. `None` disables probing.
+    ///
+    /// The probe runs while [`LibReloader::update`] holds the reloader, i.e.
+    /// calls into the library have to wait for it.
+    pub fn set_probe(&mut self, probe: Option<LibProbe>) {
+        self.probe = probe;
+    }
+
+    /// Replaces the loaded library version with the one the last reload
+    /// replaced, e.g. because the new version is broken. Returns `false` if
+    /// there is no version to return to, rolling back requires
+    /// [`LibReloader::set_rollback_enabled`] and is only possible once per
+    /// reload.
+    ///
+    /// The restored version keeps its generation. The library file is not
+    /// reloaded again until it changes.
+    pub fn rollback(&mut self) -> Result<bool, HotReloaderError> {
+        let Some(PreviousLibrary {
+            lib,
+            file,
+            generation,
+            loaded_at,
+        }) = self.previous.take()
+        else {
+            return Ok(false);
+        };
+
+        log::info!("rolling back to {file:?}");
+
+        let restored = Arc::as_ptr(&lib);
+        self.old_generations
+            .retain(|old_lib| old_lib.strong_count() > 0 && old_lib.as_ptr() != restored);
+        let current = self.lib.replace(lib);
+        let current_file = std::mem::replace(&mut self.loaded_lib_file, file);
+        self.generation.store(generation, Ordering::Release);
+        self.loaded_at = loaded_at;
+
+        if let Some(current) = current {
+            self.old_generations.push(Arc::downgrade(&current));
+            self.unload(current, current_file)?;
+        }
 
         Ok(true)
     }
 
+    /// Forgets about a pending change without reloading the library. The
+    /// currently loaded version stays in place and the next change of the
+    /// library file will be picked up by [`LibReloader::update`] again.
+    pub fn discard_change(&self) {
+        if self.changed.swap(false, Ordering::AcqRel) {
+            log::info!("discarding pending change of {:?}", self.watched_lib_file);
+        }
+    }
+
     /// Reload library `self.lib_file`.
     fn reload(&mut self) -> Result<(), HotReloaderError> {
-        let Self {
-            load_counter,
-            lib_dir,
-            lib_name,
-            watched_lib_file,
-            loaded_lib_file,
-            lib,
-            loaded_lib_name_template,
-            ..
-        } = self;
+        let watched_lib_file = self.watched_lib_file.clone();
 
         log::info!("reloading lib {watched_lib_file:?}");
 
-        // Close the loaded lib, copy the new lib to a file we can load, then load it.
-        if let Some(lib) = lib.take() {
-            lib.close()?;
-            if loaded_lib_file.exists() {
-                let _ = fs::remove_file(&loaded_lib_file);
+        // A failing probe keeps the loaded lib in place.
+        let probed_lib_file = match &self.probe {
+            Some(probe) if watched_lib_file.exists() => Some(self.probe_candidate(probe)?),
+            _ => None,
+        };
+
+        // Close (or retain) the loaded lib, copy the new lib to a file we can
+        // load, then load it.
+        if let Some(lib) = self.lib.take() {
+            // old versions are unloaded when their last guard is dropped
+            self.old_generations
+                .retain(|old_lib| old_lib.strong_count() > 0);
+            self.old_generations.push(Arc::downgrade(&lib));
+            let loaded_lib_file = self.loaded_lib_file.clone();
+            let replaced = if self.rollback_enabled {
+                self.previous.replace(PreviousLibrary {
+                    lib,
+                    file: loaded_lib_file,
+                    generation: self.generation(),
+                    loaded_at: self.loaded_at,
+                })
+            } else {
+                self.unload(lib, loaded_lib_file)?;
+                self.previous.take()
+            };
+            if let Some(PreviousLibrary { lib, file, .. }) = replaced {
+                self.unload(lib, file)?;
             }
         }
 
         if watched_lib_file.exists() {
-            *load_counter += 1;
+            self.load_counter += 1;
+            self.generation.store(self.load_counter, Ordering::Release);
             let (_, loaded_lib_file) = watched_and_loaded_library_paths(
-                lib_dir,
-                lib_name,
-                *load_counter,
-                loaded_lib_name_template,
+                &self.lib_dir,
+                &self.lib_name,
+                self.load_counter,
+                &self.loaded_lib_name_template,
             );
-            log::trace!("copy {watched_lib_file:?} -> {loaded_lib_file:?}");
-            fs::copy(watched_lib_file, &loaded_lib_file)?;
-            self.lib_file_hash
-                .store(hash_file(&loaded_lib_file), Ordering::Release);
-            #[cfg(target_os = "macos")]
-            self.codesigner.codesign(&loaded_lib_file);
-            self.lib = Some(load_library(&loaded_lib_file)?);
+            // the hash is of the unsigned copy so that it matches the hash of
+            // the watched file
+            let hash = match probed_lib_file {
+                Some((probed_lib_file, hash)) => {
+                    log::trace!("rename {probed_lib_file:?} -> {loaded_lib_file:?}");
+                    fs::rename(&probed_lib_file, &loaded_lib_file)?;
+                    hash
+                }
+                None => {
+                    log::trace!("copy {watched_lib_file:?} -> {loaded_lib_file:?}");
+                    fs::copy(&watched_lib_file, &loaded_lib_file)?;
+                    let hash = hash_file(&loaded_lib_file);
+                    #[cfg(target_os = "macos")]
+                    self.codesigner.codesign(&loaded_lib_file);
+                    hash
+                }
+            };
+            self.lib_file_hash.store(hash, Ordering::Release);
+            self.lib = Some(Arc::new(load_library(&loaded_lib_file)?));
             self.loaded_lib_file = loaded_lib_file;
+            self.loaded_at = Some(SystemTime::now());
         } else {
             log::warn!("trying to reload library but it does not exist");
         }
@@ -183,6 +464,107 @@ impl LibReloader {
         Ok(())
     }
 
+    /// Copies the watched library next to the shadow copy of the next version
+    /// and runs `probe` with it. Returns the copy that passed and its hash
+    /// before signing it, it is renamed once the loaded lib is closed so that
+    /// the shadow file name can be reused.
+    fn probe_candidate(&self, probe: &LibProbe) -> Result<(PathBuf, u32), HotReloaderError> {
+        let (watched_lib_file, loaded_lib_file) = watched_and_loaded_library_paths(
+            &self.lib_dir,
+            &self.lib_name,
+            self.load_counter + 1,
+            &self.loaded_lib_name_template,
+        );
+        let mut probed_lib_file = loaded_lib_file.into_os_string();
+        probed_lib_file.push(".probe");
+        let probed_lib_file = PathBuf::from(probed_lib_file);
+
+        log::trace!("copy {watched_lib_file:?} -> {probed_lib_file:?}");
+        fs::copy(&watched_lib_file, &probed_lib_file)?;
+        let hash = hash_file(&probed_lib_file);
+        #[cfg(target_os = "macos")]
+        self.codesigner.codesign(&probed_lib_file);
+
+        if let Err(err) = probe.run(&probed_lib_file) {
+            log::warn!("not loading {watched_lib_file:?}: {err}");
+            let _ = fs::remove_file(&probed_lib_file);
+            return Err(err);
+        }
+        Ok((probed_lib_file, hash))
+    }
+
+    /// Unloads a replaced library version `lib` loaded from `file`, unless the
+    /// leak mode or the [`TlsDestructorPolicy`] keep it loaded.
+    fn unload(&mut self, lib: Arc<Library>, file: PathBuf) -> Result<(), HotReloaderError> {
+        if self.tls_destructors && self.tls_destructor_policy == TlsDestructorPolicy::NeverUnload {
+            log::info!("{file:?} can register thread-local destructors, it is never unloaded");
+            // the strong reference is never dropped
+            std::mem::forget(lib);
+        } else {
+            let size = fs::metadata(&file).map_or(0, |meta| meta.len());
+            self.retained.push_back(RetainedLibrary {
+                lib,
+                file: file.clone(),
+                size,
+            });
+            self.unload_retained_over_limit()?;
+        }
+        if file.exists() {
+            let _ = fs::remove_file(&file);
+        }
+        Ok(())
+    }
+
+    /// Unloads the oldest retained library versions until they are within the
+    /// leak limit. Without a limit nothing is retained.
+    fn unload_retained_over_limit(&mut self) -> Result<(), HotReloaderError> {
+        let leak_limit = self.leak_limit;
+        loop {
+            let over_limit = match leak_limit {
+                None => !self.retained.is_empty(),
+                Some(LeakLimit {
+                    max_versions,
+                    max_bytes,
+                }) => {
+                    max_versions.is_some_and(|max| self.retained.len() > max)
+                        || max_bytes.is_some_and(|max| {
+                            self.retained
+                                .iter()
+                                .map(|retained| retained.size)
+                                .sum::<u64>()
+                                > max
+                        })
+                }
+            };
+            if !over_limit {
+                return Ok(());
+            }
+            let Some(RetainedLibrary { lib, file, .. }) = self.retained.pop_front() else {
+                return Ok(());
+            };
+            if let Some(limit) = leak_limit {
+                log::warn!(
+                    "leak limit {limit:?} reached, unloading {file:?}, references into it are invalid now"
+                );
+            }
+            match Arc::try_unwrap(lib) {
+                Ok(lib) => {
+                    lib.close()?;
+                    if is_mapped(&file) {
+                        log::warn!(
+                            "{file:?} is still loaded after closing it, it probably registered thread-local destructors, see TlsDestructorPolicy"
+                        );
+                        self.tls_destructors = true;
+                        self.closed_but_mapped.retain(|file| is_mapped(file));
+                        self.closed_but_mapped.push(file);
+                    }
+                }
+                // unloaded when the last `GenerationGuard` is dropped
+                Err(_) => log::debug!("{file:?} is still in use, deferring unload"),
+            }
+        }
+    }
+
     /// Watch for changes of `lib_file`.
     fn watch(
         lib_file: impl AsRef<Path>,
@@ -317,6 +699,36 @@ impl LibReloader {
         }
     }
 
+    /// Get a reference to a static variable by symbol name. Unlike
+    /// [`LibReloader::get_symbol`] the returned [`HotStatic`] keeps the
+    /// library version loaded while it is alive.
+    ///
+    /// # Safety
+    ///
+    /// Users of this API must specify the correct type of the variable and the
+    /// variable must not be mutated while it is accessed.
+    pub unsafe fn get_static<T>(&self, name: &[u8]) -> Result<HotStatic<T>, HotReloaderError> {
+        let guard = self
+            .generation_guard()
+            .ok_or(HotReloaderError::LibraryNotLoaded)?;
+        let ptr = unsafe { *guard._lib.get::<*const T>(name)? };
+        Ok(HotStatic {
+            ptr,
+            _guard: Some(guard),
+        })
+    }
+
+    /// Bundles `value` with the currently loaded library version. The library
+    /// stays loaded until the returned [`KeepLoaded`] is dropped, even if a
+    /// newer version is loaded in the meantime. Used for the futures of async
+    /// hot functions whose code lives in the library.
+    pub fn keep_loaded<T>(&self, value: T) -> KeepLoaded<T> {
+        KeepLoaded {
+            value,
+            guard: self.generation_guard(),
+        }
+    }
+
     /// Helper to log from the macro without requiring the user to have the log
     /// crate around
     #[doc(hidden)]
@@ -325,6 +737,185 @@ impl LibReloader {
     }
 }
 
+/// A value that keeps a version of the library loaded, see
+/// [`LibReloader::keep_loaded`]. Awaiting it awaits the wrapped future.
+pub struct KeepLoaded<T> {
+    // fields are dropped in order: the value before the library
+    value: T,
+    guard: Option<GenerationGuard>,
+}
+
+impl<T> KeepLoaded<T> {
+    /// Returns the wrapped value, the library might be unloaded afterwards.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+
+    /// The guard of the library version the value belongs to, `None` if no
+    /// library was loaded.
+    pub fn guard(&self) -> Option<&GenerationGuard> {
+        self.guard.as_ref()
+    }
+}
+
+/// Keeps a version ("generation") of the library loaded, see
+/// [`LibReloader::generation_guard`]. The version is only unloaded after its
+/// last guard was dropped, so values pointing into its code or data, like a
+/// `&'static str` returned by a hot function, stay valid while they are kept
+/// together with a guard.
+#[derive(Clone)]
+pub struct GenerationGuard {
+    _lib: Arc<Library>,
+    generation: usize,
+    current: Arc<AtomicUsize>,
+}
+
+impl GenerationGuard {
+    /// The library version, counting reloads like [`LibInfo::version`]. A
+    /// version restored by [`LibReloader::rollback`] keeps its generation.
+    pub fn generation(&self) -> usize {
+        self.generation
+    }
+
+    /// Is the guarded version the currently loaded one? Hot function calls
+    /// made after the guard was created and before this returns `true` used
+    /// the guarded version.
+    pub fn is_current(&self) -> bool {
+        self.current.load(Ordering::Acquire) == self.generation
+    }
+}
+
+impl std::fmt::Debug for GenerationGuard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GenerationGuard")
+            .field("generation", &self.generation)
+            .finish()
+    }
+}
+
+impl<F: Future + Unpin> Future for KeepLoaded<F> {
+    type Output = F::Output;
+
+    fn poll(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Self::Output> {
+        std::pin::Pin::new(&mut self.value).poll(cx)
+    }
+}
+
+/// A reference to a static variable of the library, see
+/// [`LibReloader::get_static`]. Dereferences to the value of the library
+/// version that was loaded when the guard was created. That version stays
+/// loaded until the guard is dropped.
+pub struct HotStatic<T> {
+    ptr: *const T,
+    _guard: Option<GenerationGuard>,
+}
+
+// Safety: like `&T`
+unsafe impl<T: Sync> Send for HotStatic<T> {}
+unsafe impl<T: Sync> Sync for HotStatic<T> {}
+
+impl<T> std::ops::Deref for HotStatic<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // Safety: the library the static lives in is kept loaded by `_guard`
+        // or the static is not part of a reloadable library
+        unsafe { &*self.ptr }
+    }
+}
+
+/// A static that is not part of a reloadable library, e.g. when reloading is
+/// disabled.
+impl<T: Sync> From<&'static T> for HotStatic<T> {
+    fn from(value: &'static T) -> Self {
+        Self {
+            ptr: value,
+            _guard: None,
+        }
+    }
+}
+
+impl<T: std::fmt::Debug> std::fmt::Debug for HotStatic<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(&**self, f)
+    }
+}
+
+/// A trait object created by the library that is re-created from the new
+/// library version after a reload. Returned by the functions declared with
+/// `#[hot_trait]` in a [`hot_module`](crate::hot_module).
+///
+/// Mutable access (e.g. calling a `&mut self` method) checks for a reload
+/// first. If there was one, the state of the current object is extracted with
+/// the `migrate` method of the trait and passed to the constructor of the new
+/// library version. Shared access returns the current object, which might
+/// still be the one of the previous library version. That version stays loaded
+/// until its object is replaced.
+pub struct HotDyn<T: ?Sized> {
+    // dropped before the reload state, like `KeepLoaded`
+    inner: KeepLoaded<Box<T>>,
+    reload: Option<(UpdateCursor, Recreate<T>)>,
+}
+
+/// Creates the object of a [`HotDyn`] from the state of the previous one.
+type Recreate<T> = fn(&T) -> KeepLoaded<Box<T>>;
+
+impl<T: ?Sized> HotDyn<T> {
+    /// Used by the `hot_module` macro. `updates` needs to be created before
+    /// `inner` so that a reload while constructing `inner` is not missed.
+    #[doc(hidden)]
+    pub fn new(updates: UpdateCursor, inner: KeepLoaded<Box<T>>, recreate: Recreate<T>) -> Self {
+        Self {
+            inner,
+            reload: Some((updates, recreate)),
+        }
+    }
+
+    /// Re-creates the object from the currently loaded library version if the
+    /// library was reloaded since the object was created. Returns `true` if
+    /// the object was replaced.
+    pub fn refresh(&mut self) -> bool {
+        let Some((updates, recreate)) = &self.reload else {
+            return false;
+        };
+        if !updates.changed_since_last_check() {
+            return false;
+        }
+        // the old object (and its library version) is dropped after the new
+        // one was created from its state
+        self.inner = recreate(&self.inner.value);
+        true
+    }
+}
+
+/// An object that is never re-created, e.g. when reloading is disabled.
+impl<T: ?Sized> From<Box<T>> for HotDyn<T> {
+    fn from(value: Box<T>) -> Self {
+        Self {
+            inner: KeepLoaded { value, guard: None },
+            reload: None,
+        }
+    }
+}
+
+impl<T: ?Sized> std::ops::Deref for HotDyn<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.inner.value
+    }
+}
+
+impl<T: ?Sized> std::ops::DerefMut for HotDyn<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.refresh();
+        &mut self.inner.value
+    }
+}
+
 /// Deletes the currently loaded lib file if it exists
 impl Drop for LibReloader {
     fn drop(&mut self) {
@@ -408,6 +999,12 @@ fn find_file_or_dir_in_parent_directories(
     }
 }
 
+/// Only detected on Linux, see [`TlsDestructorPolicy`].
+#[cfg(not(target_os = "linux"))]
+fn is_mapped(_lib_file: impl AsRef<Path>) -> bool {
+    false
+}
+
 fn load_library(lib_file: impl AsRef<Path>) -> Result<Library, HotReloaderError> {
     Ok(unsafe { Library::new(lib_file.as_ref()) }?)
 }
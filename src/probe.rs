@@ -0,0 +1,116 @@
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::error::HotReloaderError;
+
+/// Name of the probe executable built from `src/bin/hot-lib-reloader-probe.rs`.
+static PROBE_BIN: &str = "hot-lib-reloader-probe";
+
+/// Overrides where the probe executable is found.
+static PROBE_BIN_VAR: &str = "HOT_LIB_RELOADER_PROBE";
+
+/// Verifies a new library version in a separate process before it is loaded,
+/// see [`LibReloader::set_probe`](crate::LibReloader::set_probe).
+///
+/// The probe executable `hot-lib-reloader-probe` ships with this crate. It
+/// loads the library with all symbols bound immediately, looks up `symbols`
+/// and exits. A library with a crashing initializer or undefined symbols makes
+/// the probe fail instead of the running process.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LibProbe {
+    /// The probe executable. If `None`, the `HOT_LIB_RELOADER_PROBE`
+    /// environment variable is used, then a `hot-lib-reloader-probe` next to
+    /// (or one directory above) the running executable and finally the one in
+    /// `PATH`.
+    pub program: Option<PathBuf>,
+    /// Symbols the library needs to export, e.g. the names of hot functions.
+    pub symbols: Vec<String>,
+    /// How long to wait for the probe before killing it and failing the
+    /// reload. `None` waits until it exits.
+    pub timeout: Option<Duration>,
+}
+
+impl LibProbe {
+    /// Runs the probe with the library `lib_file`.
+    pub(crate) fn run(&self, lib_file: &Path) -> Result<(), HotReloaderError> {
+        let program = self.program.clone().unwrap_or_else(default_program);
+        log::debug!("probing {lib_file:?} with {program:?}");
+
+        let mut child = Command::new(&program)
+            .arg(lib_file)
+            .args(&self.symbols)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|err| {
+                HotReloaderError::ProbeFailed(format!("cannot run probe {program:?}: {err}"))
+            })?;
+
+        // a full pipe would block the probe, read it while waiting
+        let stderr = child.stderr.take().map(|mut pipe| {
+            thread::spawn(move || {
+                let mut stderr = String::new();
+                let _ = pipe.read_to_string(&mut stderr);
+                stderr
+            })
+        });
+
+        let started = Instant::now();
+        let status = loop {
+            if let Some(status) = child.try_wait()? {
+                break status;
+            }
+            if self
+                .timeout
+                .is_some_and(|timeout| started.elapsed() > timeout)
+            {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(HotReloaderError::ProbeFailed(format!(
+                    "probe did not exit within {}ms",
+                    started.elapsed().as_millis()
+                )));
+            }
+            thread::sleep(Duration::from_millis(5));
+        };
+
+        if status.success() {
+            log::debug!("probe succeeded after {}ms", started.elapsed().as_millis());
+            return Ok(());
+        }
+
+        let stderr = stderr
+            .and_then(|reader| reader.join().ok())
+            .unwrap_or_default();
+        let stderr = stderr.trim();
+        Err(HotReloaderError::ProbeFailed(if stderr.is_empty() {
+            format!("probe {status}")
+        } else {
+            format!("probe {status}: {stderr}")
+        }))
+    }
+}
+
+/// Where to find the probe executable if [`LibProbe::program`] is not set.
+fn default_program() -> PathBuf {
+    if let Some(program) = std::env::var_os(PROBE_BIN_VAR) {
+        return PathBuf::from(program);
+    }
+
+    // cargo puts examples and tests one directory below the binaries
+    let file_name = format!("{PROBE_BIN}{}", std::env::consts::EXE_SUFFIX);
+    let exe_dir = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(Path::to_path_buf));
+    exe_dir
+        .iter()
+        .flat_map(|dir| [Some(dir.as_path()), dir.parent()])
+        .flatten()
+        .map(|dir| dir.join(&file_name))
+        .find(|program| program.exists())
+        .unwrap_or_else(|| PathBuf::from(PROBE_BIN))
+}
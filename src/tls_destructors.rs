@@ -0,0 +1,37 @@
+use std::path::{Path, PathBuf};
+
+/// Is `lib_file` mapped into the process, e.g. because `dlclose` did not
+/// unload it? glibc keeps a library that registered destructors of
+/// thread-local variables (through `__cxa_thread_atexit_impl`) loaded until
+/// the threads that did so exit.
+///
+/// `lib_file` may already be deleted, `/proc/self/maps` lists it with a
+/// `(deleted)` suffix then.
+pub(crate) fn is_mapped(lib_file: impl AsRef<Path>) -> bool {
+    let lib_file = absolute_path(lib_file.as_ref());
+    let lib_file = lib_file.to_string_lossy();
+    let deleted = format!("{lib_file} (deleted)");
+    std::fs::read_to_string("/proc/self/maps").is_ok_and(|maps| {
+        maps.lines()
+            .any(|line| line.ends_with(&*lib_file) || line.ends_with(&deleted))
+    })
+}
+
+/// The path the dynamic linker reports for `lib_file`. Only the directory is
+/// canonicalized as the file itself might not exist anymore.
+fn absolute_path(lib_file: &Path) -> PathBuf {
+    let canonical = lib_file
+        .parent()
+        .zip(lib_file.file_name())
+        .and_then(|(dir, file_name)| {
+            let dir = if dir.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                dir
+            };
+            Some(dir.canonicalize().ok()?.join(file_name))
+        });
+    canonical
+        .or_else(|| std::path::absolute(lib_file).ok())
+        .unwrap_or_else(|| lib_file.to_path_buf())
+}
@@ -0,0 +1,16 @@
+mod common;
+
+#[hot_lib_reloader::hot_module(dylib = "lib_for_testing")]
+mod hot_lib {
+    hot_functions_from_file!("tests/lib_for_testing/src/lib.rs");
+
+    pub use lib_for_testing::{Counter, Greeter, Point};
+}
+
+#[test]
+fn test() {
+    let _lock = common::lock_file("tests/lib_for_testing/src/lib.rs");
+
+    // an extern "C" function taking a #[repr(C)] struct by value
+    assert_eq!(hot_lib::manhattan_length(hot_lib::Point { x: -1, y: 2 }), 3);
+}
@@ -0,0 +1,16 @@
+mod common;
+
+#[hot_lib_reloader::hot_module(dylib = "lib_for_testing")]
+mod hot_lib {
+    hot_functions_from_file!("tests/lib_for_testing/src/lib.rs");
+
+    pub use lib_for_testing::{Counter, Greeter, Point};
+}
+
+#[test]
+fn test() {
+    let _lock = common::lock_file("tests/lib_for_testing/src/lib.rs");
+
+    // tuple and wildcard patterns as arguments
+    assert_eq!(hot_lib::sum_pair((1, 2), ()), 3);
+}
@@ -0,0 +1,67 @@
+mod common;
+
+use std::task::{Context, Poll, Waker};
+
+#[hot_lib_reloader::hot_module(dylib = "lib_for_testing", file_watch_debounce = 50)]
+mod hot_lib {
+    hot_functions_from_file!("tests/lib_for_testing/src/lib.rs");
+
+    pub use lib_for_testing::{Counter, Greeter, Point};
+
+    #[lib_change_subscription]
+    pub fn subscribe() -> hot_lib_reloader::LibReloadObserver {}
+}
+
+fn poll_once<F: Future + Unpin>(future: &mut F) -> Poll<F::Output> {
+    std::pin::Pin::new(future).poll(&mut Context::from_waker(Waker::noop()))
+}
+
+fn assert_send<T: Send>(_: &T) {}
+
+#[test]
+fn test() {
+    let _lock = common::lock_file("tests/lib_for_testing/src/lib.rs");
+
+    assert_eq!(
+        poll_once(&mut hot_lib::name_len_plus(1, "ab")),
+        Poll::Ready(3)
+    );
+    assert_eq!(
+        poll_once(&mut hot_lib::shared_name_len("abc")),
+        Poll::Ready(3)
+    );
+
+    // a future that is in flight while the library is reloaded
+    let mut yielded = false;
+    let mut in_flight = hot_lib::do_stuff_after(Box::pin(std::future::poll_fn(move |_| {
+        if std::mem::replace(&mut yielded, true) {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    })));
+    assert_eq!(poll_once(&mut in_flight), Poll::Pending);
+    // declared with `#[hot_function(send)]`
+    assert_send(&in_flight);
+    let in_flight = std::panic::AssertUnwindSafe(in_flight);
+
+    common::modify_file_and_do(
+        "tests/lib_for_testing/src/lib.rs",
+        |content| {
+            content.replace(
+                "pub fn do_stuff() -> i32 { 3 }",
+                "pub fn do_stuff() -> i32 { 5 }",
+            )
+        },
+        || {
+            let lib_observer = hot_lib::subscribe();
+            common::recompile("tests/lib_for_testing");
+            lib_observer.wait_for_reload();
+            assert_eq!(hot_lib::do_stuff(), 5);
+
+            // the old library version is still loaded for the pending future
+            let mut in_flight = in_flight;
+            assert_eq!(poll_once(&mut *in_flight), Poll::Ready(3));
+        },
+    );
+}
@@ -0,0 +1,16 @@
+mod common;
+
+#[hot_lib_reloader::hot_module(dylib = "lib_for_testing")]
+mod hot_lib {
+    hot_functions_from_file!("tests/lib_for_testing/src/lib.rs");
+
+    pub use lib_for_testing::{Counter, Greeter, Point};
+}
+
+#[test]
+fn test() {
+    let _lock = common::lock_file("tests/lib_for_testing/src/lib.rs");
+
+    // only the `#[cfg(not(test))]` variant is picked up from the file
+    assert_eq!(hot_lib::build_kind(), "library");
+}
@@ -1,3 +1,6 @@
+// every test binary compiles this module, not all of them use every helper
+#![allow(dead_code)]
+
 pub fn recompile(dir: impl AsRef<std::path::Path>) {
     let cmd = std::process::Command::new("cargo")
         .arg("build")
@@ -10,12 +13,42 @@ pub fn recompile(dir: impl AsRef<std::path::Path>) {
     println!("DONE");
 }
 
+thread_local! {
+    static FILE_LOCKED: std::cell::Cell<bool> = const { std::cell::Cell::new(false) };
+}
+
+/// Lock of [`lock_file`], released when dropped (also on panic).
+pub struct FileLock(std::fs::File);
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        FILE_LOCKED.set(false);
+        let _ = self.0.unlock();
+    }
+}
+
+/// Test binaries run in parallel (e.g. with `cargo nextest`) and share the
+/// files they modify and the library they rebuild from them. Tests that do so
+/// hold this lock for their whole run. Returns `None` if this thread holds it
+/// already.
+#[must_use]
+pub fn lock_file(file: impl AsRef<std::path::Path>) -> Option<FileLock> {
+    if FILE_LOCKED.get() {
+        return None;
+    }
+    let lock = std::fs::File::open(file).expect("cannot open file to lock");
+    lock.lock().expect("cannot lock file");
+    FILE_LOCKED.set(true);
+    Some(FileLock(lock))
+}
+
 pub fn modify_file_and_do(
     file: impl AsRef<std::path::Path>,
     modify_file_fn: impl FnOnce(&str) -> String,
     do_fn: impl FnOnce() + std::panic::UnwindSafe,
 ) {
     let file = file.as_ref().canonicalize().expect("cannot find lib file");
+    let _lock = lock_file(&file);
 
     let content = std::fs::read_to_string(&file).expect("cannot read file");
     let new_content = modify_file_fn(content.as_str());
@@ -24,6 +57,10 @@ pub fn modify_file_and_do(
     let res = std::panic::catch_unwind(do_fn);
 
     std::fs::write(&file, content).expect("cannot restore file");
+    // the next test expects the library built from the original file
+    if let Some(crate_dir) = file.ancestors().find(|dir| dir.join("Cargo.toml").exists()) {
+        recompile(crate_dir);
+    }
 
     res.expect("modify_file_and_do: do_fn panicked");
 }
@@ -0,0 +1,14 @@
+// the library name in the attribute is wrong, the environment overrides it
+#[hot_lib_reloader::hot_module(dylib = "no_such_lib")]
+mod hot_lib {
+    #[hot_function]
+    pub fn sum_pair(pair: (i32, i32), unit: ()) -> i32 {}
+}
+
+#[test]
+fn test() {
+    // Safety: no other threads are running yet
+    unsafe { std::env::set_var("HOT_LIB_NAME_HOT_LIB", "lib_for_testing") };
+
+    assert_eq!(hot_lib::sum_pair((1, 2), ()), 3);
+}
@@ -0,0 +1,50 @@
+mod common;
+
+use std::time::Duration;
+
+use hot_lib_reloader::{EventQueue, ReloadEvents, SubscriptionOptions};
+
+#[hot_lib_reloader::hot_module(dylib = "lib_for_testing", file_watch_debounce = 50)]
+mod hot_lib {
+    #[hot_function]
+    pub fn do_stuff() -> i32 {}
+
+    #[lib_change_subscription]
+    pub fn subscribe_with(
+        options: hot_lib_reloader::SubscriptionOptions,
+    ) -> hot_lib_reloader::LibReloadObserver {
+    }
+}
+
+#[test]
+fn test() {
+    let _lock = common::lock_file("tests/lib_for_testing/src/lib.rs");
+    assert_eq!(hot_lib::do_stuff(), 3);
+
+    common::modify_file_and_do(
+        "tests/lib_for_testing/src/lib.rs",
+        |content| {
+            content.replace(
+                "pub fn do_stuff() -> i32 { 3 }",
+                "pub fn do_stuff() -> i32 { 5 }",
+            )
+        },
+        || {
+            // neither observer gets about-to-reload tokens, they do not hold
+            // up the reload although nobody waits on them yet
+            let reload_observer = hot_lib::subscribe_with(
+                SubscriptionOptions::default().events(ReloadEvents::RELOADED),
+            );
+            let latest_observer = hot_lib::subscribe_with(
+                SubscriptionOptions::default()
+                    .events(ReloadEvents::RELOADED)
+                    .queue(EventQueue::Latest),
+            );
+            common::recompile("tests/lib_for_testing");
+
+            assert!(reload_observer.wait_for_reload_timeout(Duration::from_secs(10)));
+            assert_eq!(hot_lib::do_stuff(), 5);
+            assert!(latest_observer.wait_for_reload_timeout(Duration::from_secs(1)));
+        },
+    );
+}
@@ -0,0 +1,51 @@
+mod common;
+
+#[hot_lib_reloader::hot_module(dylib = "lib_for_testing", file_watch_debounce = 50)]
+mod hot_lib {
+    #[hot_function]
+    pub fn build_kind() -> &'static str {}
+
+    #[lib_generation_guard]
+    pub fn generation_guard() -> Option<hot_lib_reloader::GenerationGuard> {}
+
+    #[lib_change_subscription]
+    pub fn subscribe() -> hot_lib_reloader::LibReloadObserver {}
+
+    #[lib_info]
+    pub fn info() -> hot_lib_reloader::LibInfo {}
+}
+
+#[test]
+fn test() {
+    let _lock = common::lock_file("tests/lib_for_testing/src/lib.rs");
+    // the guard is taken first, the call used its version if it is still current
+    let guard = hot_lib::generation_guard().expect("library is loaded");
+    let kind = hot_lib::build_kind();
+    assert!(guard.is_current());
+    assert_eq!(guard.generation(), 0);
+    assert_eq!(hot_lib::info().resident_generations, 1);
+
+    common::modify_file_and_do(
+        "tests/lib_for_testing/src/lib.rs",
+        |content| {
+            content.replace(
+                "pub fn do_even_more_stuff() -> i32 { 3 }",
+                "pub fn do_even_more_stuff() -> i32 { 9 }",
+            )
+        },
+        move || {
+            let lib_observer = hot_lib::subscribe();
+            common::recompile("tests/lib_for_testing");
+            lib_observer.wait_for_reload();
+
+            // the old version stays loaded for the guard
+            assert!(!guard.is_current());
+            assert_eq!(hot_lib::info().resident_generations, 2);
+            assert_eq!(kind, "library");
+
+            drop(guard);
+            assert_eq!(hot_lib::info().resident_generations, 1);
+            assert_eq!(hot_lib::generation_guard().unwrap().generation(), 1);
+        },
+    );
+}
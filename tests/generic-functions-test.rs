@@ -0,0 +1,17 @@
+mod common;
+
+#[hot_lib_reloader::hot_module(dylib = "lib_for_testing")]
+mod hot_lib {
+    hot_functions_from_file!("tests/lib_for_testing/src/lib.rs");
+
+    pub use lib_for_testing::{Counter, Greeter, Point};
+}
+
+#[test]
+fn test() {
+    let _lock = common::lock_file("tests/lib_for_testing/src/lib.rs");
+
+    // one symbol per listed type
+    assert_eq!(hot_lib::double(2), 4);
+    assert_eq!(hot_lib::double(1.5), 3.0);
+}
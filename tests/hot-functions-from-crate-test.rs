@@ -0,0 +1,56 @@
+mod common;
+
+#[hot_lib_reloader::hot_module(dylib = "lib_for_testing", file_watch_debounce = 50)]
+mod hot_lib {
+    hot_functions_from_crate!("tests/lib_for_testing");
+
+    pub use lib_for_testing::{Counter, Greeter, Point};
+
+    #[lib_change_subscription]
+    pub fn subscribe() -> hot_lib_reloader::LibReloadObserver {}
+}
+
+#[test]
+fn test() {
+    let _lock = common::lock_file("tests/lib_for_testing/src/lib.rs");
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("trace"))
+        .is_test(true)
+        .init();
+
+    assert_eq!(hot_lib::do_stuff(), 3);
+    assert_eq!(hot_lib::nested::nested_stuff(), 10);
+    assert_eq!(hot_lib::nested::inner::inner_stuff(), 11);
+    // re-exported from the library root
+    assert_eq!(hot_lib::inner_stuff(), 11);
+    // a type and a function re-exported from a private module
+    let mut state = hot_lib::State { steps: 0 };
+    hot_lib::step(&mut state);
+    assert_eq!(state.steps, 1);
+
+    let counter = hot_lib::Counter::new(2);
+    assert_eq!(hot_lib::nested::counter_plus(&counter, 1), 3);
+
+    common::modify_file_and_do(
+        "tests/lib_for_testing/src/nested.rs",
+        |content| {
+            content.replace(
+                "pub fn nested_stuff() -> i32 { 10 }",
+                "pub fn nested_stuff() -> i32 { 20 }",
+            )
+        },
+        || {
+            let lib_observer = hot_lib::subscribe();
+
+            std::thread::spawn(|| {
+                std::thread::sleep(std::time::Duration::from_millis(100));
+                common::recompile("tests/lib_for_testing");
+            });
+
+            lib_observer.wait_for_reload();
+
+            assert_eq!(hot_lib::nested::nested_stuff(), 20);
+            assert_eq!(hot_lib::nested::inner::inner_stuff(), 21);
+            assert_eq!(hot_lib::inner_stuff(), 21);
+        },
+    );
+}
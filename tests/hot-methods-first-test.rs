@@ -0,0 +1,31 @@
+mod common;
+
+#[hot_lib_reloader::hot_module(dylib = "lib_for_testing", file_watch_debounce = 50)]
+mod hot_lib {
+    hot_functions_from_file!("tests/lib_for_testing/src/lib.rs");
+
+    pub use lib_for_testing::{Counter, Greeter, Point};
+}
+
+#[test]
+fn test() {
+    let _lock = common::lock_file("tests/lib_for_testing/src/lib.rs");
+
+    common::modify_file_and_do(
+        "tests/lib_for_testing/src/lib.rs",
+        |content| {
+            content.replace(
+                "pub fn do_stuff() -> i32 { 3 }",
+                "pub fn do_stuff() -> i32 { 5 }",
+            )
+        },
+        || {
+            common::recompile("tests/lib_for_testing");
+
+            // no hot function was called yet, the method still runs the
+            // library that was just built and not the statically linked code
+            let counter = hot_lib::Counter::new(1);
+            assert_eq!(counter.value(), 6);
+        },
+    );
+}
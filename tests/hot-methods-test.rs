@@ -0,0 +1,39 @@
+mod common;
+
+#[hot_lib_reloader::hot_module(dylib = "lib_for_testing", file_watch_debounce = 50)]
+mod hot_lib {
+    hot_functions_from_file!("tests/lib_for_testing/src/lib.rs");
+
+    pub use lib_for_testing::{Counter, Greeter, Point};
+
+    #[lib_change_subscription]
+    pub fn subscribe() -> hot_lib_reloader::LibReloadObserver {}
+}
+
+#[test]
+fn test() {
+    let _lock = common::lock_file("tests/lib_for_testing/src/lib.rs");
+    assert_eq!(hot_lib::do_stuff(), 3);
+
+    let mut counter = hot_lib::Counter::new(1);
+    counter.add((1, 1));
+    assert_eq!(counter.value(), 6);
+
+    common::modify_file_and_do(
+        "tests/lib_for_testing/src/lib.rs",
+        |content| {
+            content.replace(
+                "pub fn do_stuff() -> i32 { 3 }",
+                "pub fn do_stuff() -> i32 { 5 }",
+            )
+        },
+        || {
+            let lib_observer = hot_lib::subscribe();
+            common::recompile("tests/lib_for_testing");
+            lib_observer.wait_for_reload();
+
+            // the methods of the new version work with the existing value
+            assert_eq!(counter.value(), 8);
+        },
+    );
+}
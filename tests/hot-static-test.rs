@@ -0,0 +1,37 @@
+mod common;
+
+#[hot_lib_reloader::hot_module(dylib = "lib_for_testing", file_watch_debounce = 50)]
+mod hot_lib {
+    #[hot_static]
+    pub static SPEED_TABLE: [i32; 3];
+
+    #[lib_change_subscription]
+    pub fn subscribe() -> hot_lib_reloader::LibReloadObserver {}
+}
+
+#[test]
+fn test() {
+    let _lock = common::lock_file("tests/lib_for_testing/src/lib.rs");
+
+    let speed_table = hot_lib::SPEED_TABLE();
+    assert_eq!(*speed_table, [1, 2, 3]);
+
+    common::modify_file_and_do(
+        "tests/lib_for_testing/src/lib.rs",
+        |content| {
+            content.replace(
+                "pub static SPEED_TABLE: [i32; 3] = [1, 2, 3];",
+                "pub static SPEED_TABLE: [i32; 3] = [4, 5, 6];",
+            )
+        },
+        || {
+            let lib_observer = hot_lib::subscribe();
+            common::recompile("tests/lib_for_testing");
+            lib_observer.wait_for_reload();
+
+            // statics of the old library version stay valid while borrowed
+            assert_eq!(*speed_table, [1, 2, 3]);
+            assert_eq!(*hot_lib::SPEED_TABLE(), [4, 5, 6]);
+        },
+    );
+}
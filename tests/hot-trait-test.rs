@@ -0,0 +1,59 @@
+mod common;
+
+#[hot_lib_reloader::hot_module(dylib = "lib_for_testing", file_watch_debounce = 50)]
+mod hot_lib {
+    use lib_for_testing::Greeter;
+
+    #[hot_trait(migrate = Greeter::greetings)]
+    pub fn new_greeter(greetings: usize) -> hot_lib_reloader::HotDyn<dyn Greeter> {}
+
+    #[lib_change_subscription]
+    pub fn subscribe() -> hot_lib_reloader::LibReloadObserver {}
+}
+
+// reloading is disabled, the object is created by the statically linked library
+#[hot_lib_reloader::hot_module(
+    dylib = "lib_for_testing",
+    static_fallback = "lib_for_testing",
+    enable = cfg(any())
+)]
+mod static_lib {
+    use lib_for_testing::Greeter;
+
+    #[hot_trait(migrate = Greeter::greetings)]
+    pub fn new_greeter(greetings: usize) -> hot_lib_reloader::HotDyn<dyn Greeter> {}
+}
+
+#[test]
+fn test() {
+    let _lock = common::lock_file("tests/lib_for_testing/src/lib.rs");
+    let mut static_greeter = static_lib::new_greeter(0);
+    assert_eq!(static_greeter.greet(), "hello #1");
+    assert!(!static_greeter.refresh());
+
+    let mut greeter = hot_lib::new_greeter(1);
+    assert_eq!(greeter.greet(), "hello #2");
+    assert_eq!(greeter.greetings(), 2);
+    assert!(!greeter.refresh());
+    let mut greeter = std::panic::AssertUnwindSafe(greeter);
+
+    common::modify_file_and_do(
+        "tests/lib_for_testing/src/lib.rs",
+        |content| content.replace("format!(\"hello #{}\"", "format!(\"hi #{}\""),
+        move || {
+            let lib_observer = hot_lib::subscribe();
+            common::recompile("tests/lib_for_testing");
+            lib_observer.wait_for_reload();
+
+            // shared access still sees the object of the old version
+            assert_eq!(greeter.greetings(), 2);
+
+            // mutable access re-creates it, keeping its state
+            assert_eq!(greeter.greet(), "hi #3");
+            assert!(!greeter.refresh());
+
+            // new objects are created by the new version
+            assert_eq!(hot_lib::new_greeter(0).greet(), "hi #1");
+        },
+    );
+}
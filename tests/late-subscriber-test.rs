@@ -0,0 +1,39 @@
+mod common;
+
+#[hot_lib_reloader::hot_module(dylib = "lib_for_testing", file_watch_debounce = 50)]
+mod hot_lib {
+    #[hot_function]
+    pub fn do_stuff() -> i32 {}
+
+    #[lib_change_subscription]
+    pub fn subscribe() -> hot_lib_reloader::LibReloadObserver {}
+}
+
+#[test]
+fn test() {
+    let _lock = common::lock_file("tests/lib_for_testing/src/lib.rs");
+    assert_eq!(hot_lib::do_stuff(), 3);
+    assert_eq!(hot_lib::subscribe().subscribed_version(), 0);
+
+    common::modify_file_and_do(
+        "tests/lib_for_testing/src/lib.rs",
+        |content| {
+            content.replace(
+                "pub fn do_stuff() -> i32 { 3 }",
+                "pub fn do_stuff() -> i32 { 5 }",
+            )
+        },
+        || {
+            let lib_observer = hot_lib::subscribe();
+            common::recompile("tests/lib_for_testing");
+            lib_observer.wait_for_reload();
+            assert_eq!(hot_lib::do_stuff(), 5);
+
+            // late subscribers start at the current version and do not see
+            // the reload that already happened
+            let late_observer = hot_lib::subscribe();
+            assert_eq!(late_observer.subscribed_version(), 1);
+            assert!(!late_observer.wait_for_reload_timeout(std::time::Duration::from_millis(100)));
+        },
+    );
+}
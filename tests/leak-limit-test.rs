@@ -0,0 +1,98 @@
+mod common;
+
+use std::sync::Mutex;
+
+#[hot_lib_reloader::hot_module(
+    dylib = "lib_for_testing",
+    file_watch_debounce = 50,
+    leak_max_versions = 1
+)]
+mod hot_lib {
+    #[hot_function]
+    pub fn do_even_more_stuff() -> i32 {}
+
+    #[lib_change_subscription]
+    pub fn subscribe() -> hot_lib_reloader::LibReloadObserver {}
+
+    #[lib_info]
+    pub fn info() -> hot_lib_reloader::LibInfo {}
+}
+
+/// Records the warnings of the reloader.
+struct WarningLogger(Mutex<Vec<String>>);
+
+impl log::Log for WarningLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::Level::Warn
+    }
+
+    fn log(&self, record: &log::Record) {
+        if self.enabled(record.metadata()) {
+            self.0.lock().unwrap().push(record.args().to_string());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: WarningLogger = WarningLogger(Mutex::new(Vec::new()));
+
+#[test]
+fn test() {
+    let _lock = common::lock_file("tests/lib_for_testing/src/lib.rs");
+    log::set_logger(&LOGGER).expect("set logger");
+    log::set_max_level(log::LevelFilter::Warn);
+    let limit_warnings = || {
+        LOGGER
+            .0
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|warning| warning.contains("leak limit"))
+            .count()
+    };
+
+    hot_lib::do_even_more_stuff();
+    assert_eq!(hot_lib::info().retained_versions, 0);
+
+    let file = "tests/lib_for_testing/src/lib.rs";
+    common::modify_file_and_do(
+        file,
+        |content| {
+            content.replace(
+                "pub fn do_even_more_stuff() -> i32 { 3 }",
+                "pub fn do_even_more_stuff() -> i32 { 5 }",
+            )
+        },
+        || {
+            let lib_observer = hot_lib::subscribe();
+            common::recompile("tests/lib_for_testing");
+            lib_observer.wait_for_reload();
+            assert_eq!(hot_lib::do_even_more_stuff(), 5);
+
+            // the first version is retained, it is within the limit
+            let info = hot_lib::info();
+            assert_eq!(info.retained_versions, 1);
+            assert_eq!(info.resident_generations, 2);
+            assert_eq!(limit_warnings(), 0);
+
+            // every further reload forces the oldest retained version out
+            for (n, value) in [7, 9].into_iter().enumerate() {
+                let content = std::fs::read_to_string(file).unwrap();
+                let content = content.replace(
+                    &format!("pub fn do_even_more_stuff() -> i32 {{ {} }}", value - 2),
+                    &format!("pub fn do_even_more_stuff() -> i32 {{ {value} }}"),
+                );
+                std::fs::write(file, content).unwrap();
+                common::recompile("tests/lib_for_testing");
+                lib_observer.wait_for_reload();
+                assert_eq!(hot_lib::do_even_more_stuff(), value);
+
+                let info = hot_lib::info();
+                assert_eq!(info.retained_versions, 1);
+                assert_eq!(info.resident_generations, 2);
+                assert_eq!(limit_warnings(), n + 1);
+            }
+        },
+    );
+}
@@ -0,0 +1,45 @@
+mod common;
+
+#[hot_lib_reloader::hot_module(
+    dylib = "lib_for_testing",
+    file_watch_debounce = 50,
+    leak_max_versions = 4
+)]
+mod hot_lib {
+    #[hot_function]
+    pub fn build_kind() -> &'static str {}
+
+    #[lib_change_subscription]
+    pub fn subscribe() -> hot_lib_reloader::LibReloadObserver {}
+
+    #[lib_info]
+    pub fn info() -> hot_lib_reloader::LibInfo {}
+}
+
+#[test]
+fn test() {
+    let _lock = common::lock_file("tests/lib_for_testing/src/lib.rs");
+    // points into the data of the first library version
+    let kind = hot_lib::build_kind();
+    assert_eq!(kind, "library");
+    assert_eq!(hot_lib::info().retained_versions, 0);
+
+    common::modify_file_and_do(
+        "tests/lib_for_testing/src/lib.rs",
+        |content| {
+            content.replace(
+                "pub fn do_even_more_stuff() -> i32 { 3 }",
+                "pub fn do_even_more_stuff() -> i32 { 7 }",
+            )
+        },
+        || {
+            let lib_observer = hot_lib::subscribe();
+            common::recompile("tests/lib_for_testing");
+            lib_observer.wait_for_reload();
+
+            // the old version is still loaded
+            assert_eq!(hot_lib::info().retained_versions, 1);
+            assert_eq!(kind, "library");
+        },
+    );
+}
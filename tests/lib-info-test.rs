@@ -0,0 +1,49 @@
+mod common;
+
+#[hot_lib_reloader::hot_module(dylib = "lib_for_testing", file_watch_debounce = 50)]
+mod hot_lib {
+    #[hot_function]
+    pub fn do_stuff() -> i32 {}
+
+    #[lib_change_subscription]
+    pub fn subscribe() -> hot_lib_reloader::LibReloadObserver {}
+
+    #[lib_info]
+    pub fn info() -> hot_lib_reloader::LibInfo {}
+}
+
+#[test]
+fn test() {
+    let _lock = common::lock_file("tests/lib_for_testing/src/lib.rs");
+
+    let info = hot_lib::info();
+    assert_eq!(info.version, 0);
+    assert!(
+        info.loaded_path
+            .is_some_and(|path| path != info.watched_path)
+    );
+    assert!(info.loaded_at.is_some());
+    assert!(info.last_error.is_none());
+    assert!(!info.update_pending);
+
+    common::modify_file_and_do(
+        "tests/lib_for_testing/src/lib.rs",
+        |content| {
+            content.replace(
+                "pub fn do_stuff() -> i32 { 3 }",
+                "pub fn do_stuff() -> i32 { 5 }",
+            )
+        },
+        || {
+            let lib_observer = hot_lib::subscribe();
+            common::recompile("tests/lib_for_testing");
+            lib_observer.wait_for_reload();
+            assert_eq!(hot_lib::do_stuff(), 5);
+
+            let new_info = hot_lib::info();
+            assert_eq!(new_info.version, 1);
+            assert_ne!(new_info.hash, info.hash);
+            assert!(new_info.loaded_at > info.loaded_at);
+        },
+    );
+}
@@ -0,0 +1,22 @@
+#[hot_lib_reloader::hot_module(dylib = "lib_for_testing")]
+mod hot_lib {
+    #[hot_function]
+    pub fn sum_pair(pair: (i32, i32), unit: ()) -> i32 {}
+
+    #[lib_init]
+    pub fn init() -> Result<(), hot_lib_reloader::HotReloaderError> {}
+}
+
+#[test]
+fn test() {
+    // Safety: no other threads are running yet
+    unsafe { std::env::set_var("HOT_LIB_DIR_HOT_LIB", "no/such/dir") };
+    assert!(hot_lib::init().is_err());
+
+    // initialization can be retried
+    // Safety: see above
+    unsafe { std::env::remove_var("HOT_LIB_DIR_HOT_LIB") };
+    hot_lib::init().expect("init");
+    hot_lib::init().expect("init is idempotent");
+    assert_eq!(hot_lib::sum_pair((1, 2), ()), 3);
+}
@@ -10,6 +10,9 @@ use hot_lib_reloader as hlibr_crate_alias;
 mod hot_lib {
     hot_functions_from_file!("tests/lib_for_testing/src/lib.rs");
 
+    // types in the signatures of the library functions
+    use lib_for_testing::{Counter, Greeter, Point};
+
     #[lib_change_subscription]
     pub fn subscribe() -> super::hlibr_crate_alias::LibReloadObserver {}
 
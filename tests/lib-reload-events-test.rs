@@ -0,0 +1,169 @@
+use hot_lib_reloader::{
+    ChangedEvent, EventQueue, HotPanic, LibReloadNotifier, ReloadEvents, SubscriptionOptions,
+};
+use std::time::Duration;
+
+#[test]
+fn late_subscriber_sees_current_version_and_all_following_reloads() {
+    let notifier = LibReloadNotifier::default();
+
+    let early = notifier.subscribe();
+    assert_eq!(early.subscribed_version(), 0);
+
+    notifier.send_reloaded_event();
+    assert!(early.wait_for_reload_timeout(Duration::from_millis(100)));
+
+    let late = notifier.subscribe();
+    assert_eq!(late.subscribed_version(), 1);
+    assert!(!late.wait_for_reload_timeout(Duration::from_millis(10)));
+
+    notifier.send_reloaded_event();
+    assert_eq!(notifier.version(), 2);
+    assert!(early.wait_for_reload_timeout(Duration::from_millis(100)));
+    assert!(late.wait_for_reload_timeout(Duration::from_millis(100)));
+}
+
+#[test]
+fn events_are_delivered_while_subscribing_concurrently() {
+    let notifier = std::sync::Arc::new(LibReloadNotifier::default());
+    let observer = notifier.subscribe();
+
+    let subscriber_thread = {
+        let notifier = notifier.clone();
+        std::thread::spawn(move || (0..1000).map(|_| notifier.subscribe()).collect::<Vec<_>>())
+    };
+
+    for _ in 0..100 {
+        notifier.send_reloaded_event();
+    }
+
+    for _ in 0..100 {
+        assert!(observer.wait_for_reload_timeout(Duration::from_millis(100)));
+    }
+
+    // every observer receives exactly the reloads after its subscribed version
+    for observer in subscriber_thread.join().unwrap() {
+        for _ in observer.subscribed_version()..100 {
+            assert!(observer.wait_for_reload_timeout(Duration::from_millis(100)));
+        }
+        assert!(!observer.wait_for_reload_timeout(Duration::from_millis(0)));
+    }
+}
+
+#[test]
+fn filtered_subscribers_do_not_hold_up_reloads() {
+    let notifier = std::sync::Arc::new(LibReloadNotifier::default());
+    let observer =
+        notifier.subscribe_with(SubscriptionOptions::default().events(ReloadEvents::RELOADED));
+
+    // would block forever if the observer queued an unread BlockReload token
+    let reloader = {
+        let notifier = notifier.clone();
+        std::thread::spawn(move || {
+            notifier.send_about_to_reload_event_and_wait_for_blocks();
+            notifier.send_reloaded_event();
+        })
+    };
+    reloader.join().unwrap();
+
+    assert!(observer.wait_for_reload_timeout(Duration::from_millis(100)));
+    assert!(!observer.wait_for_reload_timeout(Duration::from_millis(0)));
+}
+
+#[test]
+#[should_panic(expected = "not subscribed")]
+fn waiting_for_filtered_out_events_panics() {
+    let notifier = LibReloadNotifier::default();
+    let observer =
+        notifier.subscribe_with(SubscriptionOptions::default().events(ReloadEvents::RELOADED));
+    observer.wait_for_about_to_reload_timeout(Duration::from_millis(0));
+}
+
+#[test]
+fn bounded_and_latest_queues_drop_events() {
+    let notifier = LibReloadNotifier::default();
+    let bounded = notifier.subscribe_with(
+        SubscriptionOptions::default()
+            .events(ReloadEvents::RELOADED | ReloadEvents::PANICKED)
+            .queue(EventQueue::Bounded(2)),
+    );
+    let latest = notifier.subscribe_with(SubscriptionOptions::default().queue(EventQueue::Latest));
+    let panic = |function: &str| HotPanic {
+        function: function.to_string(),
+        message: None,
+        version: 0,
+    };
+
+    notifier.send_panic_event(panic("first"));
+    notifier.send_panic_event(panic("second"));
+    // pushes out the oldest panic
+    notifier.send_panic_event(panic("third"));
+    // push out the remaining panics and then exceed the bound
+    for _ in 0..3 {
+        notifier.send_reloaded_event();
+    }
+    // nothing left to make room for it
+    notifier.send_panic_event(panic("fourth"));
+
+    for _ in 0..3 {
+        assert!(bounded.wait_for_reload_timeout(Duration::from_millis(100)));
+    }
+    assert_eq!(
+        bounded.wait_for_panic_timeout(Duration::from_millis(0)),
+        None
+    );
+
+    assert_eq!(
+        latest.wait_for_panic_timeout(Duration::from_millis(100)),
+        Some(panic("fourth"))
+    );
+    assert!(!latest.wait_for_reload_timeout(Duration::from_millis(0)));
+}
+
+#[test]
+fn cancelled_reload_is_reported() {
+    let notifier = std::sync::Arc::new(LibReloadNotifier::default());
+    let observer = notifier.subscribe();
+
+    let reloader = {
+        let notifier = notifier.clone();
+        std::thread::spawn(move || {
+            let cancelled = notifier.send_about_to_reload_event_and_wait_for_blocks();
+            if let Some(reason) = cancelled.clone() {
+                notifier.send_reload_cancelled_event(reason);
+            }
+            cancelled
+        })
+    };
+
+    let block = observer.wait_for_about_to_reload();
+    let other_block = block.clone();
+    block.cancel("recording in progress");
+    drop(other_block);
+
+    assert_eq!(
+        reloader.join().unwrap().as_deref(),
+        Some("recording in progress")
+    );
+    assert_eq!(
+        observer
+            .wait_for_reload_cancelled_timeout(Duration::from_millis(100))
+            .as_deref(),
+        Some("recording in progress")
+    );
+    assert_eq!(notifier.version(), 0);
+}
+
+#[test]
+#[allow(deprecated)]
+fn unbounded_observers_still_receive_events_through_rx() {
+    let notifier = LibReloadNotifier::default();
+    let observer = notifier.subscribe();
+
+    notifier.send_reloaded_event();
+
+    assert!(matches!(
+        observer.rx.recv_timeout(Duration::from_millis(100)),
+        Ok(ChangedEvent::LibReloaded)
+    ));
+}
@@ -1,3 +1,9 @@
+pub mod nested;
+mod state;
+
+pub use nested::inner::inner_stuff;
+pub use state::{State, step};
+
 #[rustfmt::skip]
 #[unsafe(no_mangle)]
 pub fn do_stuff() -> i32 { 3 }
@@ -11,3 +17,146 @@ pub fn do_more_stuff(callback: Box<dyn Fn() -> i32>) -> i32 {
 #[rustfmt::skip]
 #[unsafe(no_mangle)]
 pub fn do_even_more_stuff() -> i32 { 3 }
+
+#[rustfmt::skip]
+#[hot_lib_reloader_macro::hot_function]
+pub fn checked_div(a: i32, b: i32) -> i32 { if b == 0 { panic!("division by zero") } a / b }
+
+#[unsafe(no_mangle)]
+pub fn sum_pair((a, b): (i32, i32), _: ()) -> i32 {
+    a + b
+}
+
+#[cfg(not(test))]
+#[unsafe(no_mangle)]
+pub fn build_kind() -> &'static str {
+    "library"
+}
+
+#[cfg(test)]
+#[unsafe(no_mangle)]
+pub fn build_kind() -> &'static str {
+    "test"
+}
+
+#[rustfmt::skip]
+#[unsafe(no_mangle)]
+pub static SPEED_TABLE: [i32; 3] = [1, 2, 3];
+
+#[repr(C)]
+pub struct Point {
+    pub x: i32,
+    pub y: i32,
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn manhattan_length(point: Point) -> i32 {
+    point.x.abs() + point.y.abs()
+}
+
+pub struct Counter(pub i32);
+
+#[hot_lib_reloader_macro::hot_methods]
+impl Counter {
+    pub fn new(start: i32) -> Self {
+        Self(start)
+    }
+
+    pub fn value(&self) -> i32 {
+        self.0 + do_stuff()
+    }
+
+    pub fn add(&mut self, (a, b): (i32, i32)) {
+        self.0 += a + b;
+    }
+}
+
+#[hot_lib_reloader_macro::hot_function(instantiate = [i32, f64])]
+pub fn double<T: std::ops::Add<Output = T> + Copy>(x: T) -> T {
+    x + x
+}
+
+#[hot_lib_reloader_macro::hot_function]
+pub async fn name_len_plus(a: i32, name: &str) -> usize {
+    a as usize + name.len()
+}
+
+#[hot_lib_reloader_macro::hot_function]
+pub async fn shared_name_len(name: &str) -> usize {
+    // not `Send`
+    let len = std::rc::Rc::new(name.len());
+    std::future::ready(()).await;
+    *len
+}
+
+#[hot_lib_reloader_macro::hot_function(send)]
+pub async fn do_stuff_after(
+    signal: std::pin::Pin<Box<dyn std::future::Future<Output = ()> + Send>>,
+) -> i32 {
+    signal.await;
+    do_stuff()
+}
+
+/// Objects created by the library for `#[hot_trait]`.
+pub trait Greeter {
+    fn greet(&mut self) -> String;
+
+    /// The state carried over to the next library version.
+    fn greetings(&self) -> usize;
+}
+
+struct CountingGreeter {
+    greetings: usize,
+}
+
+impl Greeter for CountingGreeter {
+    fn greet(&mut self) -> String {
+        self.greetings += 1;
+        format!("hello #{}", self.greetings)
+    }
+
+    fn greetings(&self) -> usize {
+        self.greetings
+    }
+}
+
+#[unsafe(no_mangle)]
+pub fn new_greeter(greetings: usize) -> Box<dyn Greeter> {
+    Box::new(CountingGreeter { greetings })
+}
+
+thread_local! {
+    // has a destructor, registered with `__cxa_thread_atexit_impl` on Linux
+    static RECORDED_CALLS: std::cell::RefCell<Vec<i32>> = const { std::cell::RefCell::new(Vec::new()) };
+}
+
+#[unsafe(no_mangle)]
+pub fn record_call(n: i32) -> usize {
+    RECORDED_CALLS.with_borrow_mut(|calls| {
+        calls.push(n);
+        calls.len()
+    })
+}
+
+/// Registers a thread-local destructor for this library the way C++
+/// `thread_local`s do, glibc keeps the library loaded until the thread exits.
+#[unsafe(no_mangle)]
+pub fn register_tls_destructor() {
+    #[cfg(target_os = "linux")]
+    unsafe {
+        use std::ffi::{c_int, c_void};
+
+        unsafe extern "C" {
+            static __dso_handle: u8;
+            fn __cxa_thread_atexit_impl(
+                dtor: unsafe extern "C" fn(*mut c_void),
+                obj: *mut c_void,
+                dso_symbol: *const u8,
+            ) -> c_int;
+        }
+
+        unsafe extern "C" fn dtor(_: *mut c_void) {}
+
+        __cxa_thread_atexit_impl(dtor, std::ptr::null_mut(), &raw const __dso_handle);
+    }
+}
@@ -0,0 +1,25 @@
+use crate::Counter;
+
+#[rustfmt::skip]
+#[unsafe(no_mangle)]
+pub fn nested_stuff() -> i32 { 10 }
+
+#[unsafe(no_mangle)]
+pub fn counter_plus(counter: &Counter, n: i32) -> i32 {
+    counter.0 + n
+}
+
+pub mod inner {
+    #[unsafe(no_mangle)]
+    pub fn inner_stuff() -> i32 {
+        super::nested_stuff() + 1
+    }
+
+    #[hot_lib_reloader_macro::hot_function]
+    pub fn inner_checked_div(a: i32, b: i32) -> i32 {
+        if b == 0 {
+            panic!("inner division by zero")
+        }
+        a / b
+    }
+}
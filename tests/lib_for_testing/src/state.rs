@@ -0,0 +1,8 @@
+pub struct State {
+    pub steps: i32,
+}
+
+#[unsafe(no_mangle)]
+pub fn step(state: &mut State) {
+    state.steps += 1;
+}
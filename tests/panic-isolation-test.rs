@@ -0,0 +1,68 @@
+mod common;
+
+use std::time::Duration;
+
+#[hot_lib_reloader::hot_module(
+    dylib = "lib_for_testing",
+    file_watch_debounce = 50,
+    on_panic = "rollback"
+)]
+mod hot_lib {
+    #[hot_function]
+    pub fn checked_div(a: i32, b: i32) -> i32 {}
+
+    #[lib_generation_guard]
+    pub fn generation_guard() -> Option<hot_lib_reloader::GenerationGuard> {}
+
+    #[lib_change_subscription]
+    pub fn subscribe() -> hot_lib_reloader::LibReloadObserver {}
+}
+
+#[test]
+fn test() {
+    let _lock = common::lock_file("tests/lib_for_testing/src/lib.rs");
+    let lib_observer = hot_lib::subscribe();
+    assert_eq!(hot_lib::try_checked_div(6, 3), Ok(2));
+
+    // there is no version to roll back to, the panic is returned
+    let panic = hot_lib::try_checked_div(6, 0).unwrap_err();
+    assert_eq!(panic.function, "checked_div");
+    assert_eq!(panic.message.as_deref(), Some("division by zero"));
+    assert_eq!(panic.version, 0);
+    assert_eq!(
+        lib_observer.wait_for_panic_timeout(Duration::from_secs(1)),
+        Some(panic)
+    );
+
+    // the wrapper itself keeps unwinding into the caller
+    assert!(std::panic::catch_unwind(|| hot_lib::checked_div(6, 0)).is_err());
+    assert!(
+        lib_observer
+            .wait_for_panic_timeout(Duration::from_secs(1))
+            .is_some()
+    );
+    // unread about-to-reload events would block the reload
+    drop(lib_observer);
+
+    common::modify_file_and_do(
+        "tests/lib_for_testing/src/lib.rs",
+        |content| content.replace("if b == 0 {", "if b != 0 {"),
+        || {
+            let lib_observer = hot_lib::subscribe();
+            common::recompile("tests/lib_for_testing");
+            lib_observer.wait_for_reload();
+            assert_eq!(hot_lib::generation_guard().unwrap().generation(), 1);
+
+            // the new version panics and is replaced by the one before
+            let panic = hot_lib::try_checked_div(6, 3).unwrap_err();
+            assert_eq!(panic.version, 1);
+            assert!(lib_observer.wait_for_reload_timeout(Duration::from_secs(1)));
+            assert_eq!(
+                lib_observer.wait_for_panic_timeout(Duration::from_secs(1)),
+                Some(panic)
+            );
+            assert_eq!(hot_lib::generation_guard().unwrap().generation(), 0);
+            assert_eq!(hot_lib::checked_div(6, 3), 2);
+        },
+    );
+}
@@ -0,0 +1,87 @@
+mod common;
+
+use std::time::Duration;
+
+#[hot_lib_reloader::hot_module(dylib = "lib_for_testing", file_watch_debounce = 50, probe = true)]
+mod hot_lib {
+    #[hot_function]
+    pub fn do_even_more_stuff() -> i32 {}
+
+    #[lib_change_subscription]
+    pub fn subscribe() -> hot_lib_reloader::LibReloadObserver {}
+
+    #[lib_info]
+    pub fn info() -> hot_lib_reloader::LibInfo {}
+}
+
+#[test]
+fn test() {
+    let _lock = common::lock_file("tests/lib_for_testing/src/lib.rs");
+    // the probe checks the library and the symbols it exports
+    let watched_path = hot_lib::info().watched_path;
+    let probe = |symbols: &[&str]| {
+        std::process::Command::new(env!("CARGO_BIN_EXE_hot-lib-reloader-probe"))
+            .arg(&watched_path)
+            .args(symbols)
+            .output()
+            .expect("run probe")
+    };
+    assert!(probe(&["do_stuff", "do_even_more_stuff"]).status.success());
+    let output = probe(&["do_stuff", "not_exported"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("not_exported"));
+
+    let before = hot_lib::do_even_more_stuff();
+
+    common::modify_file_and_do(
+        "tests/lib_for_testing/src/lib.rs",
+        |content| {
+            content.replace(
+                "pub fn do_even_more_stuff() -> i32 { 3 }",
+                r#"pub fn do_even_more_stuff() -> i32 { unsafe extern "C" { fn hot_lib_probe_test_missing() -> i32; } unsafe { hot_lib_probe_test_missing() } }"#,
+            )
+        },
+        move || {
+            // the build has an undefined symbol, loading it would fail or
+            // crash on the call
+            common::recompile("tests/lib_for_testing");
+            let mut tries = 0;
+            let last_error = loop {
+                if let Some(last_error) = hot_lib::info().last_error {
+                    break last_error;
+                }
+                tries += 1;
+                assert!(tries < 600, "reload did not fail");
+                std::thread::sleep(Duration::from_millis(50));
+            };
+            assert!(
+                last_error.contains("hot_lib_probe_test_missing"),
+                "{last_error}"
+            );
+
+            // the old version is still loaded
+            let info = hot_lib::info();
+            assert_eq!(info.version, 0);
+            assert!(!info.update_pending);
+            assert_eq!(hot_lib::do_even_more_stuff(), before);
+
+            // a fixed build is loaded again
+            common::modify_file_and_do(
+                "tests/lib_for_testing/src/lib.rs",
+                |content| {
+                    content.replace(
+                        r#"unsafe extern "C" { fn hot_lib_probe_test_missing() -> i32; } unsafe { hot_lib_probe_test_missing() } }"#,
+                        "13 }",
+                    )
+                },
+                || {
+                    let lib_observer = hot_lib::subscribe();
+                    common::recompile("tests/lib_for_testing");
+                    lib_observer.wait_for_reload();
+                    assert_eq!(hot_lib::do_even_more_stuff(), 13);
+                    assert_eq!(hot_lib::info().last_error, None);
+                },
+            );
+        },
+    );
+}
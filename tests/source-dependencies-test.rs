@@ -0,0 +1,31 @@
+#[hot_lib_reloader::hot_module(dylib = "lib_for_testing")]
+mod hot_lib {
+    hot_functions_from_crate!("tests/lib_for_testing");
+
+    pub use lib_for_testing::{Counter, Greeter, Point};
+}
+
+/// The scanned library sources are included into this test, cargo lists them
+/// in the dep-info file next to the test executable and rebuilds the test if
+/// they change.
+#[test]
+fn test() {
+    let dep_info = std::env::current_exe().unwrap().with_extension("d");
+    let dep_info = std::fs::read_to_string(&dep_info)
+        .unwrap_or_else(|err| panic!("cannot read {dep_info:?}: {err}"));
+    let dependencies = dep_info
+        .lines()
+        .next()
+        .and_then(|line| line.split_once(": "))
+        .map(|(_, dependencies)| dependencies.split(' ').collect::<Vec<_>>())
+        .unwrap_or_default();
+
+    let lib_dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/lib_for_testing");
+    for file in ["Cargo.toml", "src/lib.rs", "src/nested.rs", "src/state.rs"] {
+        let file = lib_dir.join(file);
+        assert!(
+            dependencies.contains(&file.to_str().unwrap()),
+            "{file:?} is not a dependency of the test: {dependencies:?}"
+        );
+    }
+}
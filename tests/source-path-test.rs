@@ -0,0 +1,16 @@
+#[hot_lib_reloader::hot_module(dylib = "lib_for_testing")]
+mod hot_lib {
+    // relative to the crate directory
+    hot_functions_from_file!("tests/lib_for_testing/src/lib.rs");
+
+    hot_functions_from_file!("$CARGO_MANIFEST_DIR/tests/lib_for_testing/src/nested.rs");
+
+    pub use lib_for_testing::{Counter, Greeter, Point};
+}
+
+#[test]
+fn test() {
+    // other tests modify `do_stuff` and `nested_stuff`
+    assert_eq!(hot_lib::sum_pair((1, 2), ()), 3);
+    assert_eq!(hot_lib::counter_plus(&hot_lib::Counter(2), 1), 3);
+}
@@ -0,0 +1,106 @@
+// `cfg(any())` is never enabled, the module uses the statically linked library
+#[hot_lib_reloader::hot_module(
+    dylib = "lib_for_testing",
+    static_fallback = "lib_for_testing",
+    enable = cfg(any()),
+    on_panic = "try"
+)]
+mod hot_lib {
+    hot_functions_from_file!("tests/lib_for_testing/src/lib.rs");
+    hot_functions_from_file!("tests/lib_for_testing/src/nested.rs");
+
+    pub use lib_for_testing::{Counter, Greeter, Point};
+
+    #[hot_static]
+    pub static SPEED_TABLE: [i32; 3];
+
+    #[lib_change_subscription]
+    pub fn subscribe() -> hot_lib_reloader::LibReloadObserver {}
+
+    #[lib_version]
+    pub fn version() -> usize {}
+
+    #[lib_updated]
+    pub fn was_updated() -> bool {}
+
+    #[lib_update_cursor]
+    pub fn update_cursor() -> hot_lib_reloader::UpdateCursor {}
+}
+
+// the static fallback of a hot module with the functions of a whole crate
+#[hot_lib_reloader::hot_module(
+    dylib = "lib_for_testing",
+    static_fallback = "lib_for_testing",
+    enable = cfg(any()),
+    on_panic = "try"
+)]
+mod hot_crate {
+    hot_functions_from_crate!("tests/lib_for_testing");
+
+    pub use lib_for_testing::Counter;
+}
+
+// the fallback of a file outside of the library's `src` directory re-exports
+// the given library module
+#[hot_lib_reloader::hot_module(
+    dylib = "lib_for_testing",
+    static_fallback = "lib_for_testing",
+    enable = cfg(any())
+)]
+mod hot_module_path {
+    hot_functions_from_file!("tests/ui/sources/nested.rs", module = "crate::nested");
+}
+
+// `cfg(all())` is always enabled, the library is hot-reloaded
+#[hot_lib_reloader::hot_module(
+    dylib = "lib_for_testing",
+    static_fallback = "lib_for_testing",
+    enable = cfg(all())
+)]
+mod hot_enabled {
+    #[hot_function]
+    pub fn sum_pair(pair: (i32, i32), unit: ()) -> i32 {}
+
+    #[lib_version]
+    pub fn version() -> usize {}
+}
+
+#[test]
+fn test() {
+    // same functions as the hot module
+    assert_eq!(hot_lib::sum_pair((1, 2), ()), 3);
+    assert_eq!(hot_lib::double(2), 4);
+    assert_eq!(hot_lib::counter_plus(&hot_lib::Counter(2), 1), 3);
+    let speed_table: hot_lib_reloader::HotStatic<[i32; 3]> = hot_lib::SPEED_TABLE();
+    assert_eq!(*speed_table, [1, 2, 3]);
+    assert_eq!(hot_lib::try_sum_pair((1, 2), ()), Ok(3));
+    let panic = hot_lib::try_checked_div(1, 0).unwrap_err();
+    assert_eq!(panic.message.as_deref(), Some("division by zero"));
+
+    // `try_` wrappers of functions in library modules
+    assert_eq!(hot_crate::try_sum_pair((1, 2), ()), Ok(3));
+    assert_eq!(hot_crate::nested::try_nested_stuff(), Ok(10));
+    assert_eq!(
+        hot_crate::nested::try_counter_plus(&hot_crate::Counter(2), 1),
+        Ok(3)
+    );
+    assert_eq!(hot_crate::nested::inner::try_inner_checked_div(6, 2), Ok(3));
+    let panic = hot_crate::nested::inner::try_inner_checked_div(1, 0).unwrap_err();
+    assert_eq!(panic.function, "inner_checked_div");
+    assert_eq!(panic.message.as_deref(), Some("inner division by zero"));
+    // the rest of the library modules is still there
+    assert_eq!(hot_crate::nested::inner::inner_stuff(), 11);
+
+    // everything of the given library module is re-exported
+    assert_eq!(hot_module_path::nested_stuff(), 10);
+    assert_eq!(hot_module_path::inner::inner_stuff(), 11);
+
+    // no reloads
+    assert_eq!(hot_lib::version(), 0);
+    assert!(!hot_lib::was_updated());
+    assert!(!hot_lib::update_cursor().changed_since_last_check());
+    assert!(!hot_lib::subscribe().wait_for_reload_timeout(std::time::Duration::from_millis(10)));
+
+    assert_eq!(hot_enabled::sum_pair((1, 2), ()), 3);
+    assert_eq!(hot_enabled::version(), 0);
+}
@@ -0,0 +1,82 @@
+mod common;
+
+#[hot_lib_reloader::hot_module(
+    dylib = "lib_for_testing",
+    file_watch_debounce = 50,
+    tls_destructors = "never_unload"
+)]
+mod hot_lib {
+    #[hot_function]
+    pub fn record_call(n: i32) -> usize {}
+
+    #[hot_function]
+    pub fn register_tls_destructor() {}
+
+    #[lib_change_subscription]
+    pub fn subscribe() -> hot_lib_reloader::LibReloadObserver {}
+
+    #[lib_info]
+    pub fn info() -> hot_lib_reloader::LibInfo {}
+}
+
+#[test]
+fn test() {
+    let _lock = common::lock_file("tests/lib_for_testing/src/lib.rs");
+    // registers thread-local destructors on a thread that never exits, they
+    // must not run once their version is unloaded
+    let (tx, rx) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        hot_lib::register_tls_destructor();
+        tx.send(hot_lib::record_call(1)).unwrap();
+        loop {
+            std::thread::park();
+        }
+    });
+    assert_eq!(rx.recv().unwrap(), 1);
+
+    // only detected when a version stays loaded after closing it
+    let first_version = hot_lib::info().loaded_path.unwrap();
+    assert!(!hot_lib::info().tls_destructors);
+
+    let file = "tests/lib_for_testing/src/lib.rs";
+    common::modify_file_and_do(
+        file,
+        |content| {
+            content.replace(
+                "pub fn do_even_more_stuff() -> i32 { 3 }",
+                "pub fn do_even_more_stuff() -> i32 { 11 }",
+            )
+        },
+        move || {
+            let lib_observer = hot_lib::subscribe();
+            common::recompile("tests/lib_for_testing");
+            lib_observer.wait_for_reload();
+
+            // glibc keeps the first version loaded
+            let still_mapped = std::fs::read_to_string("/proc/self/maps")
+                .unwrap_or_default()
+                .contains(first_version.to_str().unwrap());
+            assert_eq!(still_mapped, cfg!(target_os = "linux"));
+            let info = hot_lib::info();
+            assert_eq!(info.tls_destructors, still_mapped);
+            assert_eq!(info.resident_generations, 1 + usize::from(still_mapped));
+
+            // once detected, later versions are never closed
+            let content = std::fs::read_to_string(file).unwrap();
+            let content = content.replace(
+                "pub fn do_even_more_stuff() -> i32 { 11 }",
+                "pub fn do_even_more_stuff() -> i32 { 13 }",
+            );
+            std::fs::write(file, content).unwrap();
+            common::recompile("tests/lib_for_testing");
+            lib_observer.wait_for_reload();
+            assert_eq!(
+                hot_lib::info().resident_generations,
+                if still_mapped { 3 } else { 1 }
+            );
+
+            // the thread-local of the new version is a different one
+            assert_eq!(hot_lib::record_call(2), 1);
+        },
+    );
+}
@@ -0,0 +1,22 @@
+#![deny(unused_must_use, deprecated)]
+
+#[hot_lib_reloader::hot_module(dylib = "lib_for_testing")]
+mod hot_lib {
+    /// Attributes and visibility are the same as declared.
+    #[hot_function]
+    #[must_use]
+    pub fn do_stuff() -> i32 {}
+
+    #[hot_function]
+    #[deprecated = "use do_stuff"]
+    pub fn do_even_more_stuff() -> i32 {}
+
+    #[hot_function]
+    pub(super) fn do_more_stuff(callback: Box<dyn Fn() -> i32>) -> i32 {}
+}
+
+fn main() {
+    hot_lib::do_stuff();
+    let _ = hot_lib::do_even_more_stuff();
+    let _ = hot_lib::do_more_stuff(Box::new(|| 1));
+}
@@ -0,0 +1,16 @@
+#[hot_lib_reloader::hot_module(dylib = "lib_for_testing")]
+mod hot_lib {
+    #[hot_function(instantiate = [i32, f64])]
+    pub fn double<T: std::ops::Add<Output = T> + Copy>(x: T) -> T {}
+}
+
+// only the hot module can add types to the instantiation list
+impl hot_lib::__hot_generic_double::Instantiated for u8 {
+    fn __hot_call(x: u8) -> u8 {
+        x + x
+    }
+}
+
+fn main() {
+    let _: fn(u8) -> u8 = hot_lib::double::<u8>;
+}
@@ -0,0 +1,7 @@
+#[hot_lib_reloader::hot_module(dylib = "lib_for_testing")]
+mod hot_lib {
+    #[hot_function]
+    pub fn show(value: impl std::fmt::Display) {}
+}
+
+fn main() {}
@@ -0,0 +1,15 @@
+// warnings about library features that are not listed and unsupported
+// predicates, denied to see them
+#![deny(deprecated)]
+
+#[hot_lib_reloader::hot_module(dylib = "lib_for_testing")]
+mod hot_lib {
+    hot_functions_from_file!("tests/ui/sources/features.rs");
+}
+
+fn main() {
+    // only the variant without the feature is there
+    let _: fn() -> i32 = hot_lib::extra;
+    // not unmangled without the feature
+    let _ = hot_lib::reloadable;
+}
@@ -0,0 +1,9 @@
+#[hot_lib_reloader::hot_module(dylib = "lib_for_testing")]
+mod hot_lib {
+    #[hot_function]
+    fn do_stuff() -> i32 {}
+}
+
+fn main() {
+    hot_lib::do_stuff();
+}
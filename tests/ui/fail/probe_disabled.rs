@@ -0,0 +1,7 @@
+#[hot_lib_reloader::hot_module(dylib = "lib_for_testing", probe = false, probe_timeout_ms = 100)]
+mod hot_lib {
+    #[hot_function]
+    pub fn do_stuff() -> i32 {}
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+#[hot_lib_reloader::hot_module(dylib = "lib_for_testing")]
+mod hot_lib {
+    #[hot_functions]
+    unsafe extern "Rust" {
+        pub fn value(&self) -> i32;
+    }
+}
+
+fn main() {}
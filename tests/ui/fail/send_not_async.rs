@@ -0,0 +1,7 @@
+#[hot_lib_reloader::hot_module(dylib = "lib_for_testing")]
+mod hot_lib {
+    #[hot_function(send)]
+    pub fn do_stuff() -> i32 {}
+}
+
+fn main() {}
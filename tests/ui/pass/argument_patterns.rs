@@ -0,0 +1,25 @@
+// Generated `try_` wrappers and subscription functions accept any argument
+// pattern. Wrappers are only type checked, the library is never loaded.
+
+#[hot_lib_reloader::hot_module(dylib = "lib_for_testing", on_panic = "try")]
+mod hot_lib {
+    #[hot_functions]
+    unsafe extern "Rust" {
+        pub fn sum_pair((a, b): (i32, i32), _: &str) -> i32;
+        pub fn mutable(mut x: Vec<u8>) -> usize;
+    }
+
+    #[hot_function]
+    pub fn ignored(_: u32) {}
+
+    #[lib_change_subscription]
+    pub fn subscribe_with(_: hot_lib_reloader::SubscriptionOptions) -> hot_lib_reloader::LibReloadObserver {}
+}
+
+fn main() {
+    let _: fn((i32, i32), &str) -> Result<i32, hot_lib_reloader::HotPanic> = hot_lib::try_sum_pair;
+    let _: fn(Vec<u8>) -> Result<usize, hot_lib_reloader::HotPanic> = hot_lib::try_mutable;
+    let _: fn(u32) -> Result<(), hot_lib_reloader::HotPanic> = hot_lib::try_ignored;
+    let _: fn(hot_lib_reloader::SubscriptionOptions) -> hot_lib_reloader::LibReloadObserver =
+        hot_lib::subscribe_with;
+}
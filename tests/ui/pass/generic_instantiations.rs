@@ -0,0 +1,47 @@
+// Each instantiation is exported under its own symbol name, even for types
+// whose names only differ in punctuation.
+#![allow(non_camel_case_types)]
+
+pub struct u8_;
+pub struct Vec_u8_;
+pub struct A;
+pub struct B;
+pub struct A_B;
+
+#[hot_lib_reloader::hot_function(instantiate = [Vec<u8_>, Vec_u8_, (A, B), A_B])]
+pub fn size<T>(value: T) -> usize {
+    std::mem::size_of_val(&value)
+}
+
+#[hot_lib_reloader::hot_function(instantiate = [u32])]
+pub fn first<'a, 'b, T>(a: &'a T, _b: &'b T) -> &'b T
+where
+    'a: 'b,
+    T: Copy,
+{
+    a
+}
+
+#[hot_lib_reloader::hot_module(dylib = "lib_for_testing")]
+mod hot_lib {
+    pub use super::{A, A_B, B, Vec_u8_, u8_};
+
+    #[hot_function(instantiate = [Vec<u8_>, Vec_u8_, (A, B), A_B])]
+    pub fn size<T>(value: T) -> usize {}
+
+    #[hot_function(instantiate = [u32])]
+    pub fn first<'a, 'b, T>(a: &'a T, _b: &'b T) -> &'b T
+    where
+        'a: 'b,
+        T: Copy,
+    {
+    }
+}
+
+fn main() {
+    // the library is never loaded, the wrappers are only type checked
+    let _ = hot_lib::size::<(A, B)>;
+    let _ = hot_lib::first::<u32>;
+    let _ = size(A_B);
+    let _ = first(&1, &2);
+}
@@ -0,0 +1,11 @@
+// features of the library are only taken from `features = [...]`, not from
+// the executable
+#[hot_lib_reloader::hot_module(dylib = "lib_for_testing")]
+mod hot_lib {
+    hot_functions_from_file!("tests/ui/sources/features.rs", features = ["extra", "reload"]);
+}
+
+fn main() {
+    let _: fn() -> i32 = hot_lib::extra;
+    let _: fn() -> i32 = hot_lib::reloadable;
+}
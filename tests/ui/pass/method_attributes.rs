@@ -0,0 +1,36 @@
+// Attributes of `#[hot_methods]` carry over to the generated items.
+#![deny(unused_variables)]
+
+pub struct Counter(pub i32);
+
+#[hot_lib_reloader::hot_methods]
+impl Counter {
+    #[allow(unused_variables)]
+    pub fn value(&self) -> i32 {
+        let unused = 1;
+        self.0
+    }
+
+    #[cfg(any())]
+    pub fn configured_away(&self) -> i32 {
+        does_not_exist()
+    }
+}
+
+#[hot_lib_reloader::hot_module(dylib = "lib_for_testing")]
+mod hot_lib {
+    pub use super::Counter;
+
+    #[hot_methods]
+    impl Counter {
+        pub fn value(&self) -> i32 {}
+
+        #[cfg(any())]
+        pub fn configured_away(&self) -> i32 {}
+    }
+}
+
+fn main() {
+    // calling it would load the library
+    let _: fn(&Counter) -> i32 = Counter::value;
+}
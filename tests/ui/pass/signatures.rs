@@ -0,0 +1,75 @@
+// Wrappers are only type checked, the library is never loaded.
+
+pub struct State<'a> {
+    pub name: &'a str,
+}
+
+#[hot_lib_reloader::hot_module(dylib = "lib_for_testing")]
+mod hot_lib {
+    pub use super::State;
+
+    #[hot_functions]
+    unsafe extern "Rust" {
+        pub fn ignored(_: i32, _: &str);
+        pub fn tuple((a, b): (i32, i32)) -> i32;
+        pub fn mutable(mut x: Vec<u8>) -> usize;
+        pub fn by_ref(ref x: String);
+        pub fn nested(State { name }: State<'_>) -> usize;
+    }
+
+    #[hot_functions]
+    unsafe extern "C" {
+        pub fn c_function(x: i32) -> i32;
+        pub safe fn safe_c_function(x: *const u8);
+    }
+
+    #[hot_function]
+    pub extern "C" fn rust_c_function(x: i32) -> i32 {}
+
+    #[hot_function]
+    pub unsafe fn raw(ptr: *const u8) -> u8 {}
+
+    #[hot_function]
+    pub const fn constant(x: u32) -> u32 {}
+
+    #[hot_function]
+    pub fn first<'a, 'b: 'a>(x: &'a str, _y: &'b str) -> &'a str
+    where
+        'b: 'a,
+    {
+    }
+
+    #[hot_function]
+    pub fn callback(f: &dyn for<'a> Fn(&'a str) -> &'a str) {}
+
+    #[hot_function]
+    pub fn boxed(f: Box<dyn Fn(i32) -> i32 + Send>) -> Box<dyn Iterator<Item = i32>> {}
+
+    #[hot_function]
+    pub async fn handle<'a>(_: &str, name: &'a str, f: &dyn Fn(&str) -> usize) -> &'a str {}
+
+    #[hot_function(send)]
+    pub async fn spawned(name: &str) -> usize {}
+
+    #[hot_function]
+    pub fn state<'a>(state: &mut State<'a>, name: &'a str) -> &'a str {}
+}
+
+fn main() {
+    let _: fn(i32, &str) = hot_lib::ignored;
+    let _: fn((i32, i32)) -> i32 = hot_lib::tuple;
+    let _: fn(Vec<u8>) -> usize = hot_lib::mutable;
+    let _: fn(String) = hot_lib::by_ref;
+    let _: fn(State<'_>) -> usize = hot_lib::nested;
+    let _: unsafe fn(*const u8) -> u8 = hot_lib::raw;
+    let _: fn(u32) -> u32 = hot_lib::constant;
+    let _: fn(&'static str, &'static str) -> &'static str = hot_lib::first;
+    let _: fn(&dyn for<'a> Fn(&'a str) -> &'a str) = hot_lib::callback;
+    let _: fn(Box<dyn Fn(i32) -> i32 + Send>) -> Box<dyn Iterator<Item = i32>> = hot_lib::boxed;
+    let _: for<'a> fn(&mut State<'a>, &'a str) -> &'a str = hot_lib::state;
+    let _ = hot_lib::handle;
+    let _ = hot_lib::spawned;
+    let _: unsafe fn(i32) -> i32 = hot_lib::c_function;
+    let _: fn(*const u8) = hot_lib::safe_c_function;
+    let _: fn(i32) -> i32 = hot_lib::rust_c_function;
+}
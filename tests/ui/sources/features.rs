@@ -0,0 +1,24 @@
+// scanned by the ui tests, not compiled
+
+#[cfg(feature = "extra")]
+#[unsafe(no_mangle)]
+pub fn extra() -> i32 {
+    1
+}
+
+#[cfg(not(feature = "extra"))]
+#[unsafe(no_mangle)]
+pub fn extra() -> i32 {
+    0
+}
+
+#[cfg_attr(feature = "reload", unsafe(no_mangle))]
+pub fn reloadable() -> i32 {
+    2
+}
+
+#[cfg(version("1.80"))]
+#[unsafe(no_mangle)]
+pub fn versioned() -> i32 {
+    3
+}
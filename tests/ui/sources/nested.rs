@@ -0,0 +1,7 @@
+// scanned by the tests, not compiled. Has the exports of
+// `lib_for_testing::nested` but is not located below its `src` directory.
+
+#[unsafe(no_mangle)]
+pub fn nested_stuff() -> i32 {
+    10
+}
@@ -0,0 +1,47 @@
+mod common;
+
+#[hot_lib_reloader::hot_module(dylib = "lib_for_testing", file_watch_debounce = 50)]
+mod hot_lib {
+    #[hot_function]
+    pub fn do_stuff() -> i32 {}
+
+    #[lib_change_subscription]
+    pub fn subscribe() -> hot_lib_reloader::LibReloadObserver {}
+
+    #[lib_update_cursor]
+    pub fn update_cursor() -> hot_lib_reloader::UpdateCursor {}
+}
+
+#[test]
+fn test() {
+    let _lock = common::lock_file("tests/lib_for_testing/src/lib.rs");
+    assert_eq!(hot_lib::do_stuff(), 3);
+
+    let cursor_a = hot_lib::update_cursor();
+    let cursor_b = hot_lib::update_cursor();
+    assert!(!cursor_a.changed_since_last_check());
+
+    common::modify_file_and_do(
+        "tests/lib_for_testing/src/lib.rs",
+        |content| {
+            content.replace(
+                "pub fn do_stuff() -> i32 { 3 }",
+                "pub fn do_stuff() -> i32 { 5 }",
+            )
+        },
+        || {
+            let lib_observer = hot_lib::subscribe();
+            common::recompile("tests/lib_for_testing");
+            lib_observer.wait_for_reload();
+            assert_eq!(hot_lib::do_stuff(), 5);
+
+            // every cursor sees the reload once
+            assert!(cursor_a.changed_since_last_check());
+            assert!(!cursor_a.changed_since_last_check());
+            assert!(cursor_b.changed_since_last_check());
+
+            // cursors created later start at the current version
+            assert!(!hot_lib::update_cursor().changed_since_last_check());
+        },
+    );
+}